@@ -0,0 +1,44 @@
+use actix_web::{HttpRequest, HttpResponse, Result};
+
+use crate::models::import_graph::ImportGraph;
+use crate::utils::api_error::api_error;
+
+fn read_import_graph() -> Result<ImportGraph> {
+  let graph = ImportGraph::from_database().map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not read the modlists metadata. {}",
+      err
+    ))
+  })?;
+
+  Ok(graph)
+}
+
+/// the import graph of the whole database, in JSON
+pub async fn import_graph_json(_req: HttpRequest) -> Result<HttpResponse> {
+  let graph = read_import_graph()?;
+
+  let content = serde_json::to_string_pretty(&graph).map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not serialize the import graph. {}",
+      err
+    ))
+  })?;
+
+  Ok(
+    HttpResponse::Ok()
+      .content_type("application/json")
+      .body(content),
+  )
+}
+
+/// the import graph of the whole database, in the Graphviz DOT format
+pub async fn import_graph_dot(_req: HttpRequest) -> Result<HttpResponse> {
+  let graph = read_import_graph()?;
+
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/vnd.graphviz")
+      .body(graph.to_dot()),
+  )
+}
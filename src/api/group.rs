@@ -0,0 +1,194 @@
+use actix_web::{http, web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::models::database_config::DatabaseConfig;
+use crate::models::modlist::ModList;
+use crate::utils::api_error::{api_error, ApiError};
+
+fn read_database_config() -> Result<DatabaseConfig, ApiError> {
+  DatabaseConfig::read_from_disk().map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not read the database config. {}",
+      err
+    ))
+  })
+}
+
+fn write_database_config(config: &DatabaseConfig) -> Result<(), ApiError> {
+  config.write_to_disk().map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not write the database config. {}",
+      err
+    ))
+  })
+}
+
+/// moves every modlist of the `group_name` group into the `new_group_name`
+/// group, or removes them from any group if it is `None`.
+fn regroup_modlists(group_name: &str, new_group_name: Option<&str>) -> Result<(), ApiError> {
+  for mut modlist in ModList::get_all() {
+    modlist.read_metadata_from_disk().map_err(|err| {
+      api_error(format!(
+        "Internal server error: could not read modlist metadata. {}",
+        err
+      ))
+    })?;
+
+    if modlist.group.as_deref() != Some(group_name) {
+      continue;
+    }
+
+    modlist.group = new_group_name.map(String::from);
+    modlist.write_metadata_to_disk().map_err(|err| {
+      api_error(format!(
+        "Internal server error: could not write modlist metadata. {}",
+        err
+      ))
+    })?;
+  }
+
+  Ok(())
+}
+
+fn redirect_to_groups(body: &'static str) -> HttpResponse {
+  HttpResponse::Found()
+    .append_header((http::header::LOCATION, "/groups"))
+    .content_type("text/plain")
+    .body(body)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateGroupBody {
+  pub group_name: String,
+  pub description: String,
+}
+
+pub async fn create_group(
+  _req: HttpRequest, form: web::Form<CreateGroupBody>,
+) -> Result<HttpResponse> {
+  let group_name = form.group_name.trim();
+
+  if group_name.is_empty() {
+    return Ok(
+      HttpResponse::BadRequest()
+        .content_type("text/plain")
+        .body("the group name cannot be empty"),
+    );
+  }
+
+  let mut config = read_database_config()?;
+  config.add_group(group_name, form.description.trim());
+  write_database_config(&config)?;
+
+  Ok(redirect_to_groups("group created"))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateGroupBody {
+  pub group_name: String,
+  pub new_group_name: String,
+  pub description: String,
+}
+
+/// updates the name and the description of the group. Renaming a group also
+/// updates the modlists that are in the group.
+pub async fn update_group(
+  _req: HttpRequest, form: web::Form<UpdateGroupBody>,
+) -> Result<HttpResponse> {
+  let new_group_name = form.new_group_name.trim();
+
+  if new_group_name.is_empty() {
+    return Ok(
+      HttpResponse::BadRequest()
+        .content_type("text/plain")
+        .body("the group name cannot be empty"),
+    );
+  }
+
+  let mut config = read_database_config()?;
+  let is_renamed = new_group_name != form.group_name;
+
+  if is_renamed && config.has_group(new_group_name) {
+    return Ok(
+      HttpResponse::BadRequest()
+        .content_type("text/plain")
+        .body(format!("a group named {} already exists", new_group_name)),
+    );
+  }
+
+  match config.get_group_mut(&form.group_name) {
+    Some(group) => {
+      group.name = new_group_name.to_owned();
+      group.description = form.description.trim().to_owned();
+    }
+    None => {
+      return Ok(
+        HttpResponse::NotFound()
+          .content_type("text/plain")
+          .body("no such group"),
+      );
+    }
+  };
+
+  write_database_config(&config)?;
+
+  if is_renamed {
+    regroup_modlists(&form.group_name, Some(new_group_name))?;
+  }
+
+  Ok(redirect_to_groups("group updated"))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GroupBody {
+  pub group_name: String,
+}
+
+/// deletes the group, the modlists it contained are left without group
+pub async fn delete_group(_req: HttpRequest, form: web::Form<GroupBody>) -> Result<HttpResponse> {
+  let mut config = read_database_config()?;
+  config.remove_group(&form.group_name);
+  write_database_config(&config)?;
+
+  regroup_modlists(&form.group_name, None)?;
+
+  Ok(redirect_to_groups("group deleted"))
+}
+
+pub async fn move_group_up(_req: HttpRequest, form: web::Form<GroupBody>) -> Result<HttpResponse> {
+  let mut config = read_database_config()?;
+  config.move_group_up(&form.group_name);
+  write_database_config(&config)?;
+
+  Ok(redirect_to_groups("group moved up"))
+}
+
+pub async fn move_group_down(
+  _req: HttpRequest, form: web::Form<GroupBody>,
+) -> Result<HttpResponse> {
+  let mut config = read_database_config()?;
+  config.move_group_down(&form.group_name);
+  write_database_config(&config)?;
+
+  Ok(redirect_to_groups("group moved down"))
+}
+
+/// folds or unfolds the group on the home page
+pub async fn toggle_group_collapse(
+  _req: HttpRequest, form: web::Form<GroupBody>,
+) -> Result<HttpResponse> {
+  let mut config = read_database_config()?;
+
+  if let Some(group) = config.get_group_mut(&form.group_name) {
+    group.collapsed = !group.collapsed;
+  }
+
+  write_database_config(&config)?;
+
+  Ok(
+    HttpResponse::Found()
+      .append_header((http::header::LOCATION, "/"))
+      .content_type("text/plain")
+      .body("group toggled"),
+  )
+}
@@ -1,3 +1,5 @@
+pub mod graph;
+pub mod group;
 pub mod modlist;
 pub mod program;
 pub mod socket_merge;
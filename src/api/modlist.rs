@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
@@ -7,7 +8,15 @@ use actix_web::{http, web, HttpRequest, HttpResponse, Result};
 use dirs;
 use serde::{Deserialize, Serialize};
 
+use crate::models::bundle_merge;
+use crate::models::database_config::DatabaseConfig;
+use crate::models::input_settings;
+use crate::models::merge_resolutions::MergeResolutions;
+use crate::models::merge_session::MergeSession;
 use crate::models::modlist::ModList;
+use crate::models::mods_settings::{self, ModOverride};
+use crate::models::script_merge;
+use crate::models::user_settings;
 use crate::utils::api_error::api_error;
 use crate::utils::copy_across_drives;
 
@@ -274,6 +283,64 @@ pub async fn unload_imports_modlist(
   )
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ModListReloadDependentsBody {
+  pub modlist_name: String,
+}
+
+/// reloads the imports of every modlist that imports the supplied modlist,
+/// directly or not. The dependents whose imports are not loaded are left as
+/// they are.
+pub async fn reload_dependents_imports_modlist(
+  _req: HttpRequest, form: web::Form<ModListReloadDependentsBody>,
+) -> Result<HttpResponse> {
+  let modlist = ModList::get_by_name(&form.modlist_name);
+
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body(format!("modlist {} not found", form.modlist_name)),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  let dependents = modlist.get_dependents().map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not read the modlists importing {}. {}",
+      modlist.name, err
+    ))
+  })?;
+
+  for mut dependent in dependents {
+    if !dependent.imports_loaded {
+      continue;
+    }
+
+    if let Err(err) = dependent.modlist.reload_imported_modlists() {
+      return Ok(
+        HttpResponse::InternalServerError()
+          .content_type("text/plain")
+          .body(format!(
+            "Internal server error: could not reload the imports of {}. {}",
+            dependent.modlist.name, err
+          )),
+      );
+    }
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .append_header((
+        http::header::LOCATION,
+        format!("/modlist/{}", form.modlist_name),
+      ))
+      .content_type("text/plain")
+      .body("reloaded"),
+  )
+}
+
 pub async fn initialize(_req: HttpRequest) -> Result<HttpResponse> {
   let witcher_root = Path::new(constants::WITCHER_GAME_ROOT);
 
@@ -675,6 +742,9 @@ pub async fn merge_modlist(
   )
 }
 
+/// starts merging the scripts of the modlist, or joins the merge that is already
+/// running. Responds with the id of the merge session the page opens a
+/// websocket on.
 pub async fn merge_modlist_scripts(
   _req: HttpRequest, form: web::Form<MergeModListBody>,
 ) -> Result<HttpResponse> {
@@ -688,29 +758,89 @@ pub async fn merge_modlist_scripts(
     );
   }
 
-  let modlist = modlist.unwrap();
+  let mut modlist = modlist.unwrap();
+
+  modlist.read_metadata_from_disk().map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not read modlist metadata. {}",
+      err
+    ))
+  })?;
+
+  let session = MergeSession::start(&modlist).map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not start merging the scripts. {}",
+      err
+    ))
+  })?;
 
-  println!("dsf");
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/plain")
+      .body(session.id.clone()),
+  )
+}
 
-  crate::api::socket_merge::main(modlist.name).await;
+#[derive(Serialize, Deserialize)]
+pub struct ForgetMergeResolutionBody {
+  pub modlist_name: String,
+  pub script: String,
+  pub mod_name: String,
+  pub hunk: String,
+}
 
-  println!("dsf");
+/// removes a remembered resolution so the next merge asks for it again
+pub async fn forget_merge_resolution(
+  _req: HttpRequest, form: web::Form<ForgetMergeResolutionBody>,
+) -> Result<HttpResponse> {
+  let modlist = ModList::get_by_name(&form.modlist_name);
+
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body("no such modlist"),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  let mut resolutions = MergeResolutions::read_from_disk(&modlist).map_err(|err| {
+    api_error(format!(
+      "Internal server error: could not read the resolutions. {}",
+      err
+    ))
+  })?;
+
+  if resolutions.forget(&form.script, &form.mod_name, &form.hunk) {
+    resolutions.write_to_disk(&modlist).map_err(|err| {
+      api_error(format!(
+        "Internal server error: could not write the resolutions. {}",
+        err
+      ))
+    })?;
+  }
 
   Ok(
     HttpResponse::Found()
       .append_header((
         http::header::LOCATION,
-        format!("/modlist/{}", form.modlist_name),
+        format!("/modlist/{}/resolutions", form.modlist_name),
       ))
       .content_type("text/plain")
-      .body("modlist merged"),
+      .body("resolution forgotten"),
   )
 }
 
-/// this version of the merge action uses the tw3-script-merger tool instead of
-/// scriptmerger
-pub async fn merge_modlist_scripts_old(
-  _req: HttpRequest, form: web::Form<MergeModListBody>,
+#[derive(Serialize, Deserialize)]
+pub struct MergeBundlesBody {
+  pub modlist_name: String,
+}
+
+/// merges the xml files of the bundles that conflict and rebuilds the merged
+/// bundle of the modlist.
+pub async fn merge_modlist_bundles(
+  _req: HttpRequest, form: web::Form<MergeBundlesBody>,
 ) -> Result<HttpResponse> {
   let modlist = ModList::get_by_name(&form.modlist_name);
 
@@ -724,53 +854,73 @@ pub async fn merge_modlist_scripts_old(
 
   let modlist = modlist.unwrap();
 
-  let scriptmerger_path = std::env::current_dir()
-    .unwrap()
-    .join(constants::TW3SCRIPTMERGER_PATH);
+  let report = match bundle_merge::merge_bundles(&modlist) {
+    Ok(report) => report,
+    Err(err) => {
+      return Ok(
+        HttpResponse::InternalServerError()
+          .content_type("text/plain")
+          .body(format!(
+            "Internal server error: could not merge the bundles. {}",
+            err
+          )),
+      )
+    }
+  };
 
-  let source_path = modlist.content_path();
-  let input_path = modlist.mods_path();
-  let output_path = input_path
-    .join(constants::SCRIPTMERGER_MERGEDFILES_FOLDERNAME)
-    .join("content")
-    .join("scripts");
+  for path in &report.conflicted {
+    println!("could not merge {}, the mods change the same lines", path);
+  }
 
-  use std::io::{BufRead, BufReader, Error, ErrorKind};
-  use std::process::{Command, Stdio};
-
-  let stdout = Command::new(scriptmerger_path)
-    .arg("--clean")
-    .arg("--texteditor")
-    .arg("code")
-    .arg("--source")
-    .arg(&source_path)
-    .arg("--input")
-    .arg(&input_path)
-    .arg("--output")
-    .arg(&output_path)
-    .stdout(Stdio::piped())
-    .spawn()
-    .map_err(|err| {
-      api_error(format!(
-        "Internal server error: could not run the tw3-script-merger tool. {}",
-        err
-      ))
-    })?
-    .stdout
-    .ok_or_else(|| Error::new(ErrorKind::Other, "Could not capture standard output"))
-    .map_err(|err| {
-      api_error(format!(
-        "Internal server error: an error occured when listening to the tw3-script-merger tool. {}",
-        err
+  Ok(
+    HttpResponse::Found()
+      .append_header((
+        http::header::LOCATION,
+        format!("/modlist/{}/bundle-conflicts", form.modlist_name),
       ))
-    })?;
+      .content_type("text/plain")
+      .body(format!(
+        "{} files merged, {} conflicting, {} skipped",
+        report.merged.len(),
+        report.conflicted.len(),
+        report.skipped.len()
+      )),
+  )
+}
 
-  let reader = BufReader::new(stdout);
+#[derive(Serialize, Deserialize)]
+pub struct MergeInputBody {
+  pub modlist_name: String,
+}
+
+pub async fn merge_modlist_input(
+  _req: HttpRequest, form: web::Form<MergeInputBody>,
+) -> Result<HttpResponse> {
+  let modlist = ModList::get_by_name(&form.modlist_name);
 
-  reader
-    .lines()
-    .filter_map(|line| line.ok())
-    .for_each(|line| println!("{}", line));
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body("no such modlist"),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  let report = match input_settings::merge_input_settings(&modlist) {
+    Ok(report) => report,
+    Err(err) => {
+      return Ok(
+        HttpResponse::InternalServerError()
+          .content_type("text/plain")
+          .body(format!(
+            "Internal server error: could not merge the input settings. {}",
+            err
+          )),
+      )
+    }
+  };
 
   Ok(
     HttpResponse::Found()
@@ -779,17 +929,186 @@ pub async fn merge_modlist_scripts_old(
         format!("/modlist/{}", form.modlist_name),
       ))
       .content_type("text/plain")
-      .body("modlist merged"),
+      .body(format!(
+        "{} mods with bindings, {} bindings inserted, {} removed",
+        report.mods.len(),
+        report.inserted,
+        report.removed
+      )),
+  )
+}
+
+/// stores the priorities of the form of the mods settings page. The fields are
+/// named `enabled|mod` and `priority|mod`.
+pub async fn update_mods_settings(
+  _req: HttpRequest, form: web::Form<Vec<(String, String)>>,
+) -> Result<HttpResponse> {
+  let modlist_name = form
+    .iter()
+    .find(|(name, _)| name == "modlist_name")
+    .map(|(_, value)| value.clone())
+    .unwrap_or_default();
+
+  let modlist = ModList::get_by_name(&modlist_name);
+
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body("no such modlist"),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  let mut enabled = HashMap::new();
+  let mut priorities = HashMap::new();
+
+  // the later fields win, the checkbox comes after its hidden field
+  for (name, value) in form.iter() {
+    match name.split_once('|') {
+      Some(("enabled", mod_name)) => {
+        enabled.insert(mod_name.to_owned(), value == "1");
+      }
+      Some(("priority", mod_name)) => {
+        if let Ok(priority) = value.trim().parse::<u32>() {
+          priorities.insert(mod_name.to_owned(), priority);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  let values = priorities
+    .into_iter()
+    .map(|(mod_name, priority)| {
+      let value = ModOverride {
+        enabled: enabled.get(&mod_name).cloned().unwrap_or(true),
+        priority,
+      };
+
+      (mod_name, value)
+    })
+    .collect::<HashMap<_, _>>();
+
+  if let Err(err) = mods_settings::update_mod_priorities(&modlist, &values) {
+    return Ok(
+      HttpResponse::InternalServerError()
+        .content_type("text/plain")
+        .body(format!(
+          "Internal server error: could not write the mods settings. {}",
+          err
+        )),
+    );
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .append_header((
+        http::header::LOCATION,
+        format!("/modlist/{}/mods-settings", modlist_name),
+      ))
+      .content_type("text/plain")
+      .body("mods settings written"),
+  )
+}
+
+/// writes the values of the form of a group of the settings page. The fields
+/// are named `value|group|var`, with a `original|group|var` field holding the
+/// value the page showed so only the values that changed are written.
+pub async fn update_user_settings(
+  _req: HttpRequest, form: web::Form<Vec<(String, String)>>,
+) -> Result<HttpResponse> {
+  let modlist_name = form
+    .iter()
+    .find(|(name, _)| name == "modlist_name")
+    .map(|(_, value)| value.clone())
+    .unwrap_or_default();
+
+  let modlist = ModList::get_by_name(&modlist_name);
+
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body("no such modlist"),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  let mut values = HashMap::new();
+  let mut originals = HashMap::new();
+
+  // the later fields win, the checkbox of a toggle comes after its hidden field
+  for (name, value) in form.iter() {
+    let mut parts = name.splitn(3, '|');
+
+    if let (Some(kind), Some(group), Some(var)) = (parts.next(), parts.next(), parts.next()) {
+      let key = (group.to_owned(), var.to_owned());
+
+      match kind {
+        "value" => values.insert(key, value.clone()),
+        "original" => originals.insert(key, value.clone()),
+        _ => None,
+      };
+    }
+  }
+
+  values.retain(|key, value| originals.get(key) != Some(value));
+
+  let menus = match user_settings::get_mod_menus(&modlist.name) {
+    Ok(menus) => menus,
+    Err(err) => {
+      return Ok(
+        HttpResponse::InternalServerError()
+          .content_type("text/plain")
+          .body(format!(
+            "Internal server error: could not read the menus of the modlist. {}",
+            err
+          )),
+      );
+    }
+  };
+
+  if let Err(err) = user_settings::validate_user_settings(&menus, &values) {
+    return Ok(
+      HttpResponse::BadRequest()
+        .content_type("text/plain")
+        .body(err),
+    );
+  }
+
+  if let Err(err) = user_settings::update_user_settings(&modlist, &values) {
+    return Ok(
+      HttpResponse::InternalServerError()
+        .content_type("text/plain")
+        .body(format!(
+          "Internal server error: could not write the user settings. {}",
+          err
+        )),
+    );
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .append_header((
+        http::header::LOCATION,
+        format!("/modlist/{}/settings", modlist_name),
+      ))
+      .content_type("text/plain")
+      .body(format!("{} values written", values.len())),
   )
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ModlistVisibilityUpBody {
+pub struct ResetUserSettingsBody {
   pub modlist_name: String,
+  pub group: String,
 }
 
-pub async fn modlist_visibility_up(
-  _req: HttpRequest, form: web::Form<ModlistVisibilityUpBody>,
+pub async fn reset_user_settings(
+  _req: HttpRequest, form: web::Form<ResetUserSettingsBody>,
 ) -> Result<HttpResponse> {
   let modlist = ModList::get_by_name(&form.modlist_name);
 
@@ -801,27 +1120,65 @@ pub async fn modlist_visibility_up(
     );
   }
 
-  let mut modlist = modlist.unwrap();
+  let modlist = modlist.unwrap();
 
-  if let Err(err) = modlist.read_metadata_from_disk() {
+  if let Err(err) = user_settings::reset_group(&modlist, &form.group) {
     return Ok(
       HttpResponse::InternalServerError()
         .content_type("text/plain")
         .body(format!(
-          "Internal server error: could not read modlist metadata. {}",
+          "Internal server error: could not reset the user settings. {}",
           err
         )),
     );
   }
 
-  modlist.visibility += 1;
+  Ok(
+    HttpResponse::Found()
+      .append_header((
+        http::header::LOCATION,
+        format!("/modlist/{}/settings", form.modlist_name),
+      ))
+      .content_type("text/plain")
+      .body("settings reset"),
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RestoreMergedScriptsBody {
+  pub modlist_name: String,
+}
 
-  if let Err(err) = modlist.write_metadata_to_disk() {
+/// swaps the merged scripts of the modlist with the ones from its previous merge
+pub async fn restore_merged_scripts(
+  _req: HttpRequest, form: web::Form<RestoreMergedScriptsBody>,
+) -> Result<HttpResponse> {
+  let modlist = ModList::get_by_name(&form.modlist_name);
+
+  if modlist.is_none() {
+    return Ok(
+      HttpResponse::NotFound()
+        .content_type("text/plain")
+        .body("no such modlist"),
+    );
+  }
+
+  let modlist = modlist.unwrap();
+
+  if MergeSession::is_running(&modlist.name) {
+    return Ok(
+      HttpResponse::Conflict()
+        .content_type("text/plain")
+        .body("the scripts of the modlist are being merged"),
+    );
+  }
+
+  if let Err(err) = script_merge::restore_merged_scripts_backup(&modlist) {
     return Ok(
       HttpResponse::InternalServerError()
         .content_type("text/plain")
         .body(format!(
-          "Internal server error: could not write modlist metadata. {}",
+          "Internal server error: could not restore the previous merged scripts. {}",
           err
         )),
     );
@@ -834,17 +1191,20 @@ pub async fn modlist_visibility_up(
         format!("/modlist/{}", form.modlist_name),
       ))
       .content_type("text/plain")
-      .body("visibility decreased"),
+      .body("merged scripts restored"),
   )
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ModlistVisibilityDownBody {
+pub struct SetModlistGroupBody {
   pub modlist_name: String,
+
+  /// an empty name removes the modlist from its group
+  pub group_name: String,
 }
 
-pub async fn modlist_visibility_down(
-  _req: HttpRequest, form: web::Form<ModlistVisibilityDownBody>,
+pub async fn set_modlist_group(
+  _req: HttpRequest, form: web::Form<SetModlistGroupBody>,
 ) -> Result<HttpResponse> {
   let modlist = ModList::get_by_name(&form.modlist_name);
 
@@ -869,7 +1229,36 @@ pub async fn modlist_visibility_down(
     );
   }
 
-  modlist.visibility -= 1;
+  let group_name = form.group_name.trim();
+
+  if !group_name.is_empty() {
+    let config = match DatabaseConfig::read_from_disk() {
+      Ok(config) => config,
+      Err(err) => {
+        return Ok(
+          HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!(
+              "Internal server error: could not read the database config. {}",
+              err
+            )),
+        );
+      }
+    };
+
+    if !config.has_group(group_name) {
+      return Ok(
+        HttpResponse::BadRequest()
+          .content_type("text/plain")
+          .body(format!("no group named {}", group_name)),
+      );
+    }
+  }
+
+  modlist.group = match group_name {
+    "" => None,
+    name => Some(name.to_owned()),
+  };
 
   if let Err(err) = modlist.write_metadata_to_disk() {
     return Ok(
@@ -889,7 +1278,7 @@ pub async fn modlist_visibility_down(
         format!("/modlist/{}", form.modlist_name),
       ))
       .content_type("text/plain")
-      .body("visibility increased"),
+      .body("group changed"),
   )
 }
 
@@ -977,6 +1366,30 @@ pub async fn unpack_modlist(
   )
 }
 
+/// the menus of a modlist are listed in its dx11 and dx12 file lists, and its
+/// mods are in its input.settings and mods.settings files. They are refreshed
+/// whenever one of its menus or mods is renamed, moved or removed. None of them
+/// is needed to play, the errors are logged so they don't fail the operation.
+fn refresh_generated_files(modlist: &ModList, folder_type: &str) {
+  match folder_type {
+    "menus" => {
+      if let Err(err) = modlist.update_menu_filelists() {
+        println!("could not update the menu file lists: {}", err);
+      }
+    }
+    "mods" => {
+      if let Err(err) = modlist.merge_input_settings() {
+        println!("could not merge the input settings: {}", err);
+      }
+
+      if let Err(err) = modlist.write_mods_settings() {
+        println!("could not write the mods settings: {}", err);
+      }
+    }
+    _ => {}
+  }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RenameModlistFolderBody {
   pub modlist_name: String,
@@ -1020,6 +1433,8 @@ pub async fn rename_modlist_folder(
     );
   }
 
+  refresh_generated_files(&modlist, &form.folder_type);
+
   Ok(
     HttpResponse::Found()
       .append_header((
@@ -1076,6 +1491,9 @@ pub async fn move_modlist_folder(
     );
   }
 
+  refresh_generated_files(&modlist, &form.folder_type);
+  refresh_generated_files(&new_modlist, &form.folder_type);
+
   Ok(
     HttpResponse::Found()
       .append_header((
@@ -1158,6 +1576,8 @@ pub async fn delete_modlist_folder(
     }
   }
 
+  refresh_generated_files(&modlist, &form.folder_type);
+
   Ok(
     HttpResponse::Found()
       .append_header((
@@ -1233,7 +1653,7 @@ pub async fn delete_modlist(
 
   Ok(
     HttpResponse::Found()
-      .append_header((http::header::LOCATION, format!("/",)))
+      .append_header((http::header::LOCATION, "/".to_owned()))
       .content_type("text/plain")
       .body("modlist deleted"),
   )
@@ -169,6 +169,10 @@ fn master_css_content() -> String {
   .folder-display {
   }
 
+  .search-form input[type='text'] {
+    min-width: 300px;
+  }
+
   .folder-display + a {
     transition: 0.25s all;
     
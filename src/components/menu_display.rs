@@ -1,12 +1,18 @@
 use maud::{html, Markup};
 
-pub fn menu_display(name: &str, modlist_name: &str) -> Markup {
+/// `error` is the reason the menu file could not be parsed, if it couldn't
+pub fn menu_display(name: &str, modlist_name: &str, error: Option<&str>) -> Markup {
   let mark_as_disabled = !name.ends_with(".xml");
 
   html! {
     @if mark_as_disabled {
       span.folder-display.disabled-folder { (name) }
     }
+    @else if let Some(error) = error {
+      span.folder-display.malformed-menu title={"malformed menu: " (error)} {
+        (name)
+      }
+    }
     @else {
       span.folder-display {
         (name)
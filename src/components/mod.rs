@@ -5,6 +5,7 @@ mod menu_display;
 mod mod_display;
 mod modlist_link;
 mod page;
+mod search_form;
 
 pub use dlc_display::dlc_display;
 pub use header::header;
@@ -13,3 +14,4 @@ pub use menu_display::menu_display;
 pub use mod_display::mod_display;
 pub use modlist_link::modlist_link;
 pub use page::page;
+pub use search_form::search_form;
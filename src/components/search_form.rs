@@ -0,0 +1,10 @@
+use maud::{html, Markup};
+
+pub fn search_form(query: &str) -> Markup {
+  html! {
+    form.search-form.row.center method="get" action="/search" {
+      input type="text" name="query" value=(query) placeholder="mod, dlc, menu or file path" title="for example: game/player/r4Player.ws";
+      input type="submit" value="search";
+    }
+  }
+}
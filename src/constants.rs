@@ -15,16 +15,54 @@ pub const SCRIPTMERGER_PATH: &str = "..\\scriptmerger";
 pub const SCRIPTMERGER_PATH: &str =
   "D:\\programs\\steam\\steamapps\\common\\The Witcher 3\\scriptmerger";
 
-pub const TW3SCRIPTMERGER_PATH: &str = "tw3-script-merger.exe";
-
 pub const SCRIPTMERGER_EXE_NAME: &str = "WitcherScriptMerger.exe";
 
 pub const MODLIST_CONFIG_NAME: &str = "modlist.toml";
 
+pub const DATABASE_CONFIG_NAME: &str = "database.toml";
+
+pub const DATABASE_INDEX_NAME: &str = "index.sqlite";
+
+/// the lists of the menus the game loads, one per renderer
+pub const MENU_FILELIST_NAMES: [&str; 2] = ["dx11filelist.txt", "dx12filelist.txt"];
+
+pub const INPUT_SETTINGS_NAME: &str = "input.settings";
+
+pub const USER_SETTINGS_NAME: &str = "user.settings";
+
+pub const MODS_SETTINGS_NAME: &str = "mods.settings";
+
+pub const MODLIST_MODS_SETTINGS_NAME: &str = "mods-settings.toml";
+
+/// the bindings a mod wants in the input.settings file, at the root of the mod
+/// folder.
+pub const MOD_INPUT_SNIPPET_NAME: &str = "input.settings";
+
+pub const MODLIST_INPUT_BINDINGS_NAME: &str = "input-bindings.toml";
+
 pub const MODLIST_MERGEINVENTORY_PATH: &str = "MergeInventory.xml";
 
 pub const MODLIST_MERGEDBUNDLES_PATH: &str = "mergedbundles";
 
+pub const MODLIST_MERGED_BUNDLE_NAME: &str = "blob0.bundle";
+
+/// the files the last bundle merge wrote in the `mergedbundles` folder
+pub const MODLIST_BUNDLE_MERGE_NAME: &str = "bundle-merge.toml";
+
+pub const METADATA_STORE_NAME: &str = "metadata.store";
+
+pub const MODLIST_MERGE_STAGING_PATH: &str = "merge-staging";
+
+pub const MODLIST_MERGEDSCRIPTS_BACKUP_PATH: &str = "mergedscripts-backup";
+
+pub const MODLIST_MERGE_RESOLUTIONS_NAME: &str = "merge-resolutions.toml";
+
+pub const MODLIST_MERGES_PATH: &str = "merges";
+
+pub const MODLIST_OWN_MERGE_NAME: &str = "mod_000000_mergedfiles";
+
+pub const MODLIST_MERGE_MANIFEST_NAME: &str = "merge-manifest.toml";
+
 pub const SCRIPTMERGER_MERGEDFILES_FOLDERNAME: &str = "mod0000_MergedFiles";
 
 pub const SCRIPTMERGER_MERGEDBUNDLES_PATH: &str = "Merged Bundle Content";
@@ -42,6 +42,13 @@ async fn main() -> std::io::Result<()> {
     .arg(format!("http://localhost:{}", port))
     .output()?;
 
+  if let Err(error) = models::database_config::DatabaseConfig::migrate_visibility_levels() {
+    println!(
+      "could not migrate the visibility levels to groups: {}",
+      error
+    );
+  }
+
   println!("starting server on port {}", port);
 
   HttpServer::new(|| {
@@ -63,6 +70,49 @@ async fn main() -> std::io::Result<()> {
         web::resource("/modlist/{modlist_name}/merge")
           .route(web::get().to(pages::modlist_merge::render)),
       )
+      .service(
+        web::resource("/modlist/{modlist_name}/conflicts")
+          .route(web::get().to(pages::modlist_conflicts::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/bundle-conflicts")
+          .route(web::get().to(pages::modlist_bundle_conflicts::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/strings")
+          .route(web::get().to(pages::modlist_strings::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/textures")
+          .route(web::get().to(pages::modlist_textures::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/merge-status")
+          .route(web::get().to(pages::modlist_merge_status::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/menus")
+          .route(web::get().to(pages::modlist_menus::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/mods-settings")
+          .route(web::get().to(pages::modlist_mods_settings::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/settings")
+          .route(web::get().to(pages::modlist_user_settings::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/diff")
+          .route(web::get().to(pages::script_diff::render)),
+      )
+      .service(
+        web::resource("/modlist/{modlist_name}/resolutions")
+          .route(web::get().to(pages::modlist_resolutions::render)),
+      )
+      .service(web::resource("/graph").route(web::get().to(pages::import_graph::render)))
+      .service(web::resource("/search").route(web::get().to(pages::search::render)))
+      .service(web::resource("/groups").route(web::get().to(pages::groups::render)))
       // static files
       // .service(fs::Files::new("/static", "./static"))
       // api endpoints
@@ -70,6 +120,26 @@ async fn main() -> std::io::Result<()> {
         web::scope("/api")
           .route("/program/ping", web::post().to(api::program::ping))
           .route("/program/exit", web::post().to(api::program::exit))
+          .route("/group/create", web::post().to(api::group::create_group))
+          .route("/group/delete", web::post().to(api::group::delete_group))
+          .route("/group/update", web::post().to(api::group::update_group))
+          .route("/group/move-up", web::post().to(api::group::move_group_up))
+          .route(
+            "/group/move-down",
+            web::post().to(api::group::move_group_down),
+          )
+          .route(
+            "/group/toggle-collapse",
+            web::post().to(api::group::toggle_group_collapse),
+          )
+          .route(
+            "/graph/imports.json",
+            web::get().to(api::graph::import_graph_json),
+          )
+          .route(
+            "/graph/imports.dot",
+            web::get().to(api::graph::import_graph_dot),
+          )
           .route(
             "/modlist/initialize",
             web::post().to(api::modlist::initialize),
@@ -98,6 +168,10 @@ async fn main() -> std::io::Result<()> {
             "/modlist/unload-imports",
             web::post().to(api::modlist::unload_imports_modlist),
           )
+          .route(
+            "/modlist/reload-dependents-imports",
+            web::post().to(api::modlist::reload_dependents_imports_modlist),
+          )
           .route(
             "/modlist/move-import-up",
             web::post().to(api::modlist::move_imported_modlist_up),
@@ -111,12 +185,8 @@ async fn main() -> std::io::Result<()> {
             web::post().to(api::modlist::move_imported_modlist_down),
           )
           .route(
-            "/modlist/visibility-up",
-            web::post().to(api::modlist::modlist_visibility_up),
-          )
-          .route(
-            "/modlist/visibility-down",
-            web::post().to(api::modlist::modlist_visibility_down),
+            "/modlist/set-group",
+            web::post().to(api::modlist::set_modlist_group),
           )
           .route("/modlist/view", web::post().to(api::modlist::view_modlist))
           .route(
@@ -127,6 +197,38 @@ async fn main() -> std::io::Result<()> {
             "/modlist/merge-scripts",
             web::post().to(api::modlist::merge_modlist_scripts),
           )
+          .route(
+            "/modlist/forget-resolution",
+            web::post().to(api::modlist::forget_merge_resolution),
+          )
+          .route(
+            "/modlist/merge-bundles",
+            web::post().to(api::modlist::merge_modlist_bundles),
+          )
+          .route(
+            "/modlist/merge-input",
+            web::post().to(api::modlist::merge_modlist_input),
+          )
+          .route(
+            "/modlist/mods-settings",
+            web::post().to(api::modlist::update_mods_settings),
+          )
+          .route(
+            "/modlist/user-settings",
+            web::post().to(api::modlist::update_user_settings),
+          )
+          .route(
+            "/modlist/user-settings-reset",
+            web::post().to(api::modlist::reset_user_settings),
+          )
+          .route(
+            "/modlist/restore-merged-scripts",
+            web::post().to(api::modlist::restore_merged_scripts),
+          )
+          .route(
+            "/merge/{session_id}/socket",
+            web::get().to(api::socket_merge::merge_socket),
+          )
           .route("/modlist/pack", web::post().to(api::modlist::pack_modlist))
           .route(
             "/modlist/unpack",
@@ -0,0 +1,419 @@
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// the magic every bundle starts with
+const BUNDLE_MAGIC: &[u8; 8] = b"POTATO70";
+
+/// the size of the header, the table of the entries comes right after it
+const BUNDLE_HEADER_SIZE: usize = 0x20;
+
+/// the size of an entry in the table, its path is a 256 bytes null-terminated
+/// string followed by its hash, sizes, offset, date, crc and compression.
+const BUNDLE_ENTRY_SIZE: usize = 0x140;
+const BUNDLE_ENTRY_PATH_SIZE: usize = 0x100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleCompression {
+  None,
+  Zlib,
+  Snappy,
+  Doboz,
+  Lz4,
+  Lz4Hc,
+  Unknown(u32),
+}
+
+impl BundleCompression {
+  pub fn from_u32(compression: u32) -> BundleCompression {
+    match compression {
+      0 => BundleCompression::None,
+      1 => BundleCompression::Zlib,
+      2 => BundleCompression::Snappy,
+      3 => BundleCompression::Doboz,
+      4 => BundleCompression::Lz4,
+      5 => BundleCompression::Lz4Hc,
+      compression => BundleCompression::Unknown(compression),
+    }
+  }
+
+  pub fn to_u32(self) -> u32 {
+    match self {
+      BundleCompression::None => 0,
+      BundleCompression::Zlib => 1,
+      BundleCompression::Snappy => 2,
+      BundleCompression::Doboz => 3,
+      BundleCompression::Lz4 => 4,
+      BundleCompression::Lz4Hc => 5,
+      BundleCompression::Unknown(compression) => compression,
+    }
+  }
+}
+
+impl fmt::Display for BundleCompression {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      BundleCompression::None => write!(f, "none"),
+      BundleCompression::Zlib => write!(f, "zlib"),
+      BundleCompression::Snappy => write!(f, "snappy"),
+      BundleCompression::Doboz => write!(f, "doboz"),
+      BundleCompression::Lz4 => write!(f, "lz4"),
+      BundleCompression::Lz4Hc => write!(f, "lz4hc"),
+      BundleCompression::Unknown(compression) => write!(f, "unknown ({})", compression),
+    }
+  }
+}
+
+/// a file stored in a bundle
+#[derive(Clone, Debug)]
+pub struct BundleEntry {
+  /// the path of the file in the game files, with `\` as the separator like
+  /// the game writes it.
+  pub path: String,
+
+  pub size: u32,
+  pub compressed_size: u32,
+
+  /// the offset of the file data in the bundle
+  pub offset: u32,
+  pub compression: BundleCompression,
+}
+
+impl BundleEntry {
+  /// the path used to compare the entries, the game ignores the case and
+  /// both separators are found in the bundles.
+  pub fn key(&self) -> String {
+    self.path.replace('/', "\\").to_lowercase()
+  }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+  let mut buffer = [0; 4];
+  buffer.copy_from_slice(&bytes[offset..offset + 4]);
+
+  u32::from_le_bytes(buffer)
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+  bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn invalid_data(message: &str) -> Error {
+  Error::new(ErrorKind::InvalidData, message.to_owned())
+}
+
+/// reads the table of the entries of a bundle, the content of the files is not
+/// read so it stays fast even for the multi-gigabyte bundles of the game.
+pub fn read_entries(path: &Path) -> std::io::Result<Vec<BundleEntry>> {
+  let mut file = File::open(path)?;
+  let file_size = file.metadata()?.len();
+
+  read_entries_from(&mut file, file_size)
+}
+
+fn read_entries_from(reader: &mut impl Read, file_size: u64) -> std::io::Result<Vec<BundleEntry>> {
+  let mut header = [0; BUNDLE_HEADER_SIZE];
+  reader
+    .read_exact(&mut header)
+    .map_err(|_| invalid_data("the bundle is too short"))?;
+
+  if &header[..BUNDLE_MAGIC.len()] != BUNDLE_MAGIC {
+    return Err(invalid_data("not a bundle, the header doesn't match"));
+  }
+
+  let table_size = read_u32(&header, 16) as usize;
+
+  if (BUNDLE_HEADER_SIZE + table_size) as u64 > file_size {
+    return Err(invalid_data(
+      "the table of the entries is larger than the bundle",
+    ));
+  }
+
+  let mut table = vec![0; table_size];
+  reader.read_exact(&mut table)?;
+
+  let entries = table
+    .chunks_exact(BUNDLE_ENTRY_SIZE)
+    .map(|entry| {
+      let path = &entry[..BUNDLE_ENTRY_PATH_SIZE];
+      let path_end = path
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(path.len());
+
+      BundleEntry {
+        path: String::from_utf8_lossy(&path[..path_end]).into_owned(),
+        size: read_u32(entry, 0x114),
+        compressed_size: read_u32(entry, 0x118),
+        offset: read_u32(entry, 0x11C),
+        compression: BundleCompression::from_u32(read_u32(entry, 0x13C)),
+      }
+    })
+    .filter(|entry| !entry.path.is_empty())
+    .collect();
+
+  Ok(entries)
+}
+
+/// reads the content of a file of the bundle and decompresses it
+pub fn read_entry_data(path: &Path, entry: &BundleEntry) -> std::io::Result<Vec<u8>> {
+  read_entry_data_from(&mut File::open(path)?, entry)
+}
+
+fn read_entry_data_from(
+  reader: &mut (impl Read + Seek), entry: &BundleEntry,
+) -> std::io::Result<Vec<u8>> {
+  reader.seek(SeekFrom::Start(entry.offset as u64))?;
+
+  let mut data = vec![0; entry.compressed_size as usize];
+  reader.read_exact(&mut data)?;
+
+  let size = entry.size as usize;
+  let decompressed = match entry.compression {
+    BundleCompression::None => data,
+    BundleCompression::Zlib => {
+      let mut decompressed = Vec::with_capacity(size);
+      ZlibDecoder::new(&data[..]).read_to_end(&mut decompressed)?;
+
+      decompressed
+    }
+    BundleCompression::Snappy => snap::raw::Decoder::new()
+      .decompress_vec(&data)
+      .map_err(|error| invalid_data(&error.to_string()))?,
+    BundleCompression::Lz4 | BundleCompression::Lz4Hc => {
+      lz4_flex::block::decompress(&data, size).map_err(|error| invalid_data(&error.to_string()))?
+    }
+    compression => {
+      return Err(invalid_data(&format!(
+        "the {} compression is not supported",
+        compression
+      )))
+    }
+  };
+
+  if decompressed.len() != size {
+    return Err(invalid_data(&format!(
+      "{} is {} bytes once decompressed instead of {}",
+      entry.path,
+      decompressed.len(),
+      size
+    )));
+  }
+
+  Ok(decompressed)
+}
+
+/// writes a bundle with the files, compressed with zlib. Returns the entries
+/// of the files as they are in the written bundle.
+pub fn write_bundle(path: &Path, files: &[(String, Vec<u8>)]) -> std::io::Result<Vec<BundleEntry>> {
+  let (bundle, entries) = encode_bundle(files)?;
+
+  fs::write(path, bundle)?;
+
+  Ok(entries)
+}
+
+fn encode_bundle(files: &[(String, Vec<u8>)]) -> std::io::Result<(Vec<u8>, Vec<BundleEntry>)> {
+  let table_size = files.len() * BUNDLE_ENTRY_SIZE;
+  let mut table = vec![0; table_size];
+  let mut data = Vec::new();
+  let mut entries = Vec::with_capacity(files.len());
+
+  for (i, (file_path, content)) in files.iter().enumerate() {
+    if file_path.len() >= BUNDLE_ENTRY_PATH_SIZE {
+      return Err(invalid_data(&format!("the path {} is too long", file_path)));
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    let compressed = encoder.finish()?;
+
+    let entry = BundleEntry {
+      path: file_path.clone(),
+      size: content.len() as u32,
+      compressed_size: compressed.len() as u32,
+      offset: (BUNDLE_HEADER_SIZE + table_size + data.len()) as u32,
+      compression: BundleCompression::Zlib,
+    };
+
+    // the hash, date and crc are left empty
+    let row = &mut table[i * BUNDLE_ENTRY_SIZE..(i + 1) * BUNDLE_ENTRY_SIZE];
+    row[..file_path.len()].copy_from_slice(file_path.as_bytes());
+    write_u32(row, 0x114, entry.size);
+    write_u32(row, 0x118, entry.compressed_size);
+    write_u32(row, 0x11C, entry.offset);
+    write_u32(row, 0x13C, entry.compression.to_u32());
+
+    data.extend_from_slice(&compressed);
+    entries.push(entry);
+  }
+
+  let mut header = [0; BUNDLE_HEADER_SIZE];
+  header[..BUNDLE_MAGIC.len()].copy_from_slice(BUNDLE_MAGIC);
+  write_u32(
+    &mut header,
+    8,
+    (BUNDLE_HEADER_SIZE + table_size + data.len()) as u32,
+  );
+  write_u32(&mut header, 16, table_size as u32);
+
+  let mut bundle = Vec::with_capacity(BUNDLE_HEADER_SIZE + table_size + data.len());
+  bundle.extend_from_slice(&header);
+  bundle.extend_from_slice(&table);
+  bundle.extend_from_slice(&data);
+
+  Ok((bundle, entries))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  /// a bundle with a single file stored with the compression, the data is
+  /// expected to be compressed already.
+  fn single_file_bundle(
+    file_path: &str, compression: BundleCompression, data: &[u8], size: usize,
+  ) -> Vec<u8> {
+    let mut bundle = vec![0; BUNDLE_HEADER_SIZE + BUNDLE_ENTRY_SIZE];
+    bundle[..BUNDLE_MAGIC.len()].copy_from_slice(BUNDLE_MAGIC);
+    write_u32(&mut bundle, 16, BUNDLE_ENTRY_SIZE as u32);
+
+    let row = &mut bundle[BUNDLE_HEADER_SIZE..];
+    row[..file_path.len()].copy_from_slice(file_path.as_bytes());
+    write_u32(row, 0x114, size as u32);
+    write_u32(row, 0x118, data.len() as u32);
+    write_u32(row, 0x11C, (BUNDLE_HEADER_SIZE + BUNDLE_ENTRY_SIZE) as u32);
+    write_u32(row, 0x13C, compression.to_u32());
+
+    bundle.extend_from_slice(data);
+    bundle
+  }
+
+  fn read_single_file(bundle: &[u8]) -> std::io::Result<(BundleEntry, Vec<u8>)> {
+    let mut entries = read_entries_from(&mut Cursor::new(bundle), bundle.len() as u64)?;
+    assert_eq!(entries.len(), 1);
+
+    let entry = entries.remove(0);
+    let data = read_entry_data_from(&mut Cursor::new(bundle), &entry)?;
+
+    Ok((entry, data))
+  }
+
+  const CONTENT: &[u8] = b"<redswf><definitions></definitions></redswf>";
+
+  #[test]
+  fn written_bundle_reads_back() {
+    let files = vec![
+      (
+        String::from("gameplay\\items\\def_item_weapons.xml"),
+        CONTENT.to_vec(),
+      ),
+      (
+        String::from("gameplay/abilities/monster_base_abl.xml"),
+        b"<abilities/>".to_vec(),
+      ),
+    ];
+
+    let (bundle, written) = encode_bundle(&files).unwrap();
+    let entries = read_entries_from(&mut Cursor::new(&bundle), bundle.len() as u64).unwrap();
+
+    assert_eq!(entries.len(), files.len());
+
+    for ((file_path, content), (entry, written)) in files.iter().zip(entries.iter().zip(&written)) {
+      assert_eq!(&entry.path, file_path);
+      assert_eq!(entry.offset, written.offset);
+      assert_eq!(entry.compression, BundleCompression::Zlib);
+      assert_eq!(
+        &read_entry_data_from(&mut Cursor::new(&bundle), entry).unwrap(),
+        content
+      );
+    }
+  }
+
+  #[test]
+  fn uncompressed_file_reads_back() {
+    let bundle = single_file_bundle("a.xml", BundleCompression::None, CONTENT, CONTENT.len());
+    let (entry, data) = read_single_file(&bundle).unwrap();
+
+    assert_eq!(entry.path, "a.xml");
+    assert_eq!(data, CONTENT);
+  }
+
+  #[test]
+  fn snappy_file_reads_back() {
+    let compressed = snap::raw::Encoder::new().compress_vec(CONTENT).unwrap();
+    let bundle = single_file_bundle(
+      "a.xml",
+      BundleCompression::Snappy,
+      &compressed,
+      CONTENT.len(),
+    );
+
+    assert_eq!(read_single_file(&bundle).unwrap().1, CONTENT);
+  }
+
+  #[test]
+  fn lz4_files_read_back() {
+    let compressed = lz4_flex::block::compress(CONTENT);
+
+    for compression in [BundleCompression::Lz4, BundleCompression::Lz4Hc] {
+      let bundle = single_file_bundle("a.xml", compression, &compressed, CONTENT.len());
+      let (entry, data) = read_single_file(&bundle).unwrap();
+
+      assert_eq!(entry.compression, compression);
+      assert_eq!(data, CONTENT);
+    }
+  }
+
+  #[test]
+  fn unsupported_compressions_are_errors() {
+    for compression in [BundleCompression::Doboz, BundleCompression::Unknown(9)] {
+      let bundle = single_file_bundle("a.xml", compression, CONTENT, CONTENT.len());
+      let error = read_single_file(&bundle).unwrap_err();
+
+      assert_eq!(error.kind(), ErrorKind::InvalidData);
+      assert!(error.to_string().contains(&compression.to_string()));
+    }
+  }
+
+  #[test]
+  fn wrong_decompressed_size_is_an_error() {
+    let bundle = single_file_bundle("a.xml", BundleCompression::None, CONTENT, CONTENT.len() + 1);
+
+    assert_eq!(
+      read_single_file(&bundle).unwrap_err().kind(),
+      ErrorKind::InvalidData
+    );
+  }
+
+  #[test]
+  fn truncated_header_is_an_error() {
+    let (bundle, _) = encode_bundle(&[(String::from("a.xml"), CONTENT.to_vec())]).unwrap();
+    let truncated = &bundle[..BUNDLE_HEADER_SIZE - 1];
+    let error = read_entries_from(&mut Cursor::new(truncated), truncated.len() as u64).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn truncated_table_is_an_error() {
+    let (bundle, _) = encode_bundle(&[(String::from("a.xml"), CONTENT.to_vec())]).unwrap();
+    let truncated = &bundle[..BUNDLE_HEADER_SIZE + BUNDLE_ENTRY_SIZE - 1];
+    let error = read_entries_from(&mut Cursor::new(truncated), truncated.len() as u64).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn wrong_magic_is_an_error() {
+    let (mut bundle, _) = encode_bundle(&[(String::from("a.xml"), CONTENT.to_vec())]).unwrap();
+    bundle[0] = b'X';
+
+    assert!(read_entries_from(&mut Cursor::new(&bundle), bundle.len() as u64).is_err());
+  }
+}
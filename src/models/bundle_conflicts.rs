@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::models::bundle::{read_entries, BundleEntry};
+use crate::models::bundle_merge::mergedbundles_file_path;
+use crate::models::database_index::{open_effective_entries, EffectiveEntries, EntryCategory};
+use crate::models::modlist::ModList;
+use crate::models::script_conflicts::is_mergedfiles;
+
+/// where the bundles are stored in a mod, relative to the mod folder
+const MOD_BUNDLES_PREFIX: &str = "content/";
+
+/// a bundle of a mod that contains a contested file
+#[derive(Clone, Debug)]
+pub struct BundleConflictMod {
+  pub mod_name: String,
+
+  /// the modlist the mod comes from, it is the analyzed modlist itself when the
+  /// mod is not imported.
+  pub modlist: String,
+
+  /// the path of the bundle relative to the mod folder
+  pub bundle: String,
+
+  /// the path of the bundle on the disk
+  pub path: PathBuf,
+
+  /// the entry of the file in the bundle
+  pub entry: BundleEntry,
+}
+
+/// a file of the game that is in the bundles of more than one mod
+#[derive(Clone, Debug)]
+pub struct BundleConflict {
+  /// the path of the file in the game files, as the first mod writes it
+  pub path: String,
+
+  /// the file from the bundles of the `bundles` folder the mods replace,
+  /// `None` when it is a new file more than one mod adds.
+  pub vanilla: Option<VanillaBundleFile>,
+
+  /// whether the file is in the merged bundle content of the modlist, the
+  /// conflict was then already merged.
+  pub merged: bool,
+
+  /// the mods that contain the file, in the order the game loads them. The
+  /// first one is the one the game uses when the file is not merged.
+  pub mods: Vec<BundleConflictMod>,
+}
+
+impl BundleConflict {
+  pub fn overrides_vanilla(&self) -> bool {
+    self.vanilla.is_some()
+  }
+}
+
+/// a file of the game in one of the bundles of the `bundles` folder
+#[derive(Clone, Debug)]
+pub struct VanillaBundleFile {
+  /// the path of the bundle on the disk
+  pub path: PathBuf,
+
+  pub entry: BundleEntry,
+}
+
+pub struct BundleConflictReport {
+  pub conflicts: Vec<BundleConflict>,
+
+  /// the number of mods with at least one bundle in the effective mods set
+  pub bundle_mods: usize,
+
+  /// the number of files the bundles of the mods contain, contested or not
+  pub files: usize,
+
+  /// the bundles that could not be read, with the reason
+  pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// whether the file is in the merged bundle content of the modlist, the files
+/// are stored there with the same path they have in the bundles.
+fn is_merged(modlist: &ModList, path: &str) -> bool {
+  mergedbundles_file_path(modlist, path)
+    .map(|file_path| file_path.is_file())
+    .unwrap_or(false)
+}
+
+/// lists the files that are in the bundles of more than one mod of the
+/// modlist, with the mods from the imports included. The bundles of a mod are
+/// the `.bundle` files directly in its `content` folder.
+pub fn analyze(modlist_name: &str) -> std::io::Result<BundleConflictReport> {
+  let EffectiveEntries {
+    modlist,
+    index,
+    entries: effective_entries,
+  } = open_effective_entries(modlist_name)?;
+
+  // the game loads the mods in alphabetical order, the case is ignored
+  let mut mods = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~') && !is_mergedfiles(&entry.name))
+    .collect::<Vec<_>>();
+
+  mods.sort_by_key(|entry| entry.name.to_lowercase());
+
+  let bundle_entries = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Bundle)
+    .map(|entry| (entry.modlist.as_str(), entry.name.as_str()))
+    .collect::<HashSet<_>>();
+
+  let mut sources = mods
+    .iter()
+    .map(|entry| entry.modlist.as_str())
+    .chain(bundle_entries.iter().map(|(source, _)| *source))
+    .collect::<Vec<_>>();
+
+  sources.sort();
+  sources.dedup();
+
+  // the files of every modlist the mods and bundles come from, read once per
+  // modlist.
+  let mut files_by_modlist = BTreeMap::new();
+  for source in sources {
+    files_by_modlist.insert(source, index.get_files(source)?);
+  }
+
+  let mut unreadable = Vec::new();
+  let mut vanilla_files = HashMap::new();
+
+  for file in files_by_modlist.values().flatten() {
+    if file.category != EntryCategory::Bundle
+      || !bundle_entries.contains(&(file.modlist.as_str(), file.entry.as_str()))
+    {
+      continue;
+    }
+
+    let disk_path = file.disk_path();
+    if !disk_path
+      .to_string_lossy()
+      .to_lowercase()
+      .ends_with(".bundle")
+    {
+      continue;
+    }
+
+    match read_entries(&disk_path) {
+      Ok(entries) => {
+        for entry in entries {
+          vanilla_files
+            .entry(entry.key())
+            .or_insert_with(|| VanillaBundleFile {
+              path: disk_path.clone(),
+              entry,
+            });
+        }
+      }
+      Err(error) => unreadable.push((disk_path, error.to_string())),
+    }
+  }
+
+  let mut files: BTreeMap<String, BundleConflict> = BTreeMap::new();
+  let mut bundle_mods = 0;
+
+  for entry in &mods {
+    let mod_files = files_by_modlist
+      .get(entry.modlist.as_str())
+      .map(|files| files.as_slice())
+      .unwrap_or_default();
+
+    let mut has_bundles = false;
+
+    for file in mod_files {
+      if file.category != EntryCategory::Mod || file.entry != entry.name {
+        continue;
+      }
+
+      let lowercase_path = file.path.to_lowercase();
+
+      if !lowercase_path.starts_with(MOD_BUNDLES_PREFIX)
+        || !lowercase_path.ends_with(".bundle")
+        || lowercase_path[MOD_BUNDLES_PREFIX.len()..].contains('/')
+      {
+        continue;
+      }
+
+      has_bundles = true;
+
+      let bundle_entries = match read_entries(&file.disk_path()) {
+        Ok(bundle_entries) => bundle_entries,
+        Err(error) => {
+          unreadable.push((file.disk_path(), error.to_string()));
+          continue;
+        }
+      };
+
+      for bundle_entry in bundle_entries {
+        let key = bundle_entry.key();
+
+        let conflict = files.entry(key.clone()).or_insert_with(|| BundleConflict {
+          path: bundle_entry.path.clone(),
+          vanilla: vanilla_files.get(&key).cloned(),
+          merged: is_merged(&modlist, &bundle_entry.path),
+          mods: Vec::new(),
+        });
+
+        conflict.mods.push(BundleConflictMod {
+          mod_name: entry.name.clone(),
+          modlist: entry.modlist.clone(),
+          bundle: file.path.clone(),
+          path: file.disk_path(),
+          entry: bundle_entry,
+        });
+      }
+    }
+
+    if has_bundles {
+      bundle_mods += 1;
+    }
+  }
+
+  let files_count = files.len();
+  let conflicts = files
+    .into_values()
+    .filter(|conflict| {
+      // a mod can have the file in more than one of its bundles
+      let mod_names = conflict
+        .mods
+        .iter()
+        .map(|bundle_mod| &bundle_mod.mod_name)
+        .collect::<HashSet<_>>();
+
+      mod_names.len() > 1
+    })
+    .collect();
+
+  Ok(BundleConflictReport {
+    conflicts,
+    bundle_mods,
+    files: files_count,
+    unreadable,
+  })
+}
@@ -0,0 +1,294 @@
+use encoding_rs_io::DecodeReaderBytes;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use toml;
+
+use crate::constants;
+use crate::models::bundle::{read_entry_data, write_bundle};
+use crate::models::bundle_conflicts::{self, BundleConflict};
+use crate::models::metadata_store::write_metadata_store;
+use crate::models::modlist::ModList;
+use crate::utils::diff3::{
+  has_crlf, merge3, normalize_line_endings, restore_line_endings, Merge3Chunk,
+};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// the encoding of a file, kept so the merged file is written the way the game
+/// expects it.
+#[derive(Clone, Copy, PartialEq)]
+enum TextEncoding {
+  Utf8,
+  Utf8Bom,
+  Utf16Le,
+}
+
+impl TextEncoding {
+  fn detect(bytes: &[u8]) -> TextEncoding {
+    if bytes.starts_with(UTF16LE_BOM) {
+      TextEncoding::Utf16Le
+    } else if bytes.starts_with(UTF8_BOM) {
+      TextEncoding::Utf8Bom
+    } else {
+      TextEncoding::Utf8
+    }
+  }
+
+  fn encode(&self, text: &str) -> Vec<u8> {
+    match self {
+      TextEncoding::Utf8 => text.as_bytes().to_vec(),
+      TextEncoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+      TextEncoding::Utf16Le => UTF16LE_BOM
+        .iter()
+        .cloned()
+        .chain(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()))
+        .collect(),
+    }
+  }
+}
+
+fn decode_text(bytes: &[u8]) -> std::io::Result<String> {
+  let mut decoder = DecodeReaderBytes::new(bytes);
+
+  let mut text = String::new();
+  decoder.read_to_string(&mut text)?;
+
+  Ok(text)
+}
+
+/// the contested files of the last bundle merge of a modlist
+#[derive(Default)]
+pub struct BundleMergeReport {
+  /// the xml files that were merged into the merged bundle
+  pub merged: Vec<String>,
+
+  /// the xml files whose changes conflict, the game still uses the version of
+  /// the first mod.
+  pub conflicted: Vec<String>,
+
+  /// the contested files that are not xml and can't be merged as text, or
+  /// whose content could not be read
+  pub skipped: Vec<String>,
+}
+
+/// the files the last bundle merge wrote in the `mergedbundles` folder. The
+/// folder also has the merged bundle content of the WitcherScriptMerger, only
+/// the files listed here are replaced by the next merge.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct BundleMergeRecord {
+  #[serde(default)]
+  pub files: Vec<String>,
+}
+
+impl BundleMergeRecord {
+  pub fn path(modlist: &ModList) -> PathBuf {
+    modlist.path().join(constants::MODLIST_BUNDLE_MERGE_NAME)
+  }
+
+  pub fn read_from_disk(modlist: &ModList) -> std::io::Result<BundleMergeRecord> {
+    let path = BundleMergeRecord::path(modlist);
+
+    if !path.exists() {
+      return Ok(BundleMergeRecord::default());
+    }
+
+    let text = fs::read_to_string(path)?;
+    let record: BundleMergeRecord = toml::from_str(&text)?;
+
+    Ok(record)
+  }
+
+  pub fn write_to_disk(&self, modlist: &ModList) -> std::io::Result<()> {
+    let content = toml::to_string_pretty(&self).map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "bundle merge serialization error",
+      )
+    })?;
+
+    fs::write(BundleMergeRecord::path(modlist), content)
+  }
+}
+
+/// the path of the file in the `mergedbundles` folder, it has the same path as
+/// in the bundles. `None` when the path of the bundle would leave the folder,
+/// with a `..` or a drive prefix for example.
+pub fn mergedbundles_file_path(modlist: &ModList, path: &str) -> Option<PathBuf> {
+  let mut file_path = modlist.mergedbundles_path();
+
+  for component in path.split(['\\', '/']) {
+    if component.is_empty() {
+      continue;
+    }
+
+    let mut components = Path::new(component).components();
+    let is_normal = matches!(components.next(), Some(Component::Normal(_)))
+      && components.next().is_none()
+      && !component.contains(':');
+
+    if !is_normal {
+      return None;
+    }
+
+    file_path.push(component);
+  }
+
+  Some(file_path)
+}
+
+/// merges the versions of the file from every mod with the vanilla version as
+/// the common ancestor. Returns `None` when two mods changed the same lines.
+fn merge_file(conflict: &BundleConflict) -> std::io::Result<Option<Vec<u8>>> {
+  let (original, mut encoding) = match &conflict.vanilla {
+    Some(vanilla) => {
+      let bytes = read_entry_data(&vanilla.path, &vanilla.entry)?;
+
+      (decode_text(&bytes)?, Some(TextEncoding::detect(&bytes)))
+    }
+    None => (String::new(), None),
+  };
+
+  // the files are merged with `\n` line endings, the output gets the line
+  // endings of the vanilla file back, or those of the first mod.
+  let mut crlf = conflict.vanilla.as_ref().map(|_| has_crlf(&original));
+  let original = normalize_line_endings(&original);
+
+  let mut merged: Option<String> = None;
+
+  for bundle_mod in &conflict.mods {
+    let bytes = read_entry_data(&bundle_mod.path, &bundle_mod.entry)?;
+    let theirs = decode_text(&bytes)?;
+
+    encoding.get_or_insert_with(|| TextEncoding::detect(&bytes));
+    crlf.get_or_insert_with(|| has_crlf(&theirs));
+
+    let theirs = normalize_line_endings(&theirs);
+
+    let ours = match &merged {
+      Some(ours) => ours,
+      None => {
+        merged = Some(theirs);
+        continue;
+      }
+    };
+
+    let mut output = String::with_capacity(ours.len());
+
+    for chunk in merge3(&original, ours, &theirs) {
+      match chunk {
+        Merge3Chunk::Resolved(lines) => output.extend(lines),
+        Merge3Chunk::Conflict { .. } => return Ok(None),
+      }
+    }
+
+    merged = Some(output);
+  }
+
+  let encoding = encoding.unwrap_or(TextEncoding::Utf8);
+  let crlf = crlf.unwrap_or(false);
+
+  Ok(merged.map(|merged| encoding.encode(&restore_line_endings(merged, crlf))))
+}
+
+/// merges the xml files more than one mod has in its bundles, then rebuilds the
+/// merged bundle of the modlist and its `metadata.store` from them. The merged
+/// files are written to the `mergedbundles` folder as well, they replace the
+/// files of the previous merge but not the ones another tool put there.
+pub fn merge_bundles(modlist: &ModList) -> std::io::Result<BundleMergeReport> {
+  let conflicts = bundle_conflicts::analyze(&modlist.name)?.conflicts;
+
+  let mut report = BundleMergeReport::default();
+  let mut files = Vec::new();
+
+  for conflict in &conflicts {
+    if !conflict.path.to_lowercase().ends_with(".xml")
+      || mergedbundles_file_path(modlist, &conflict.path).is_none()
+    {
+      report.skipped.push(conflict.path.clone());
+      continue;
+    }
+
+    // an entry that can't be read, like the compression formats the bundle
+    // reader doesn't support, doesn't stop the other merges
+    match merge_file(conflict) {
+      Ok(Some(content)) => {
+        report.merged.push(conflict.path.clone());
+        files.push((conflict.path.clone(), content));
+      }
+      Ok(None) => report.conflicted.push(conflict.path.clone()),
+      Err(error) => {
+        println!("could not merge {}: {}", conflict.path, error);
+        report.skipped.push(conflict.path.clone());
+      }
+    }
+  }
+
+  // the files of the previous merge are replaced, the other files are the
+  // merges of the WitcherScriptMerger and are left as they are.
+  let mut record = BundleMergeRecord::read_from_disk(modlist)?;
+
+  for path in &record.files {
+    let file_path = match mergedbundles_file_path(modlist, path) {
+      Some(file_path) => file_path,
+      None => continue,
+    };
+
+    if file_path.is_file() {
+      fs::remove_file(file_path)?;
+    }
+  }
+
+  record.files.clear();
+
+  for (path, content) in &files {
+    let file_path = match mergedbundles_file_path(modlist, path) {
+      Some(file_path) => file_path,
+      None => continue,
+    };
+
+    // a merge of the same file by the WitcherScriptMerger is kept
+    if file_path.exists() {
+      continue;
+    }
+
+    if let Some(parent) = file_path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    fs::write(file_path, content)?;
+    record.files.push(path.clone());
+  }
+
+  record.write_to_disk(modlist)?;
+
+  let content_path = modlist.own_merge_path().join("content");
+  let bundle_path = content_path.join(constants::MODLIST_MERGED_BUNDLE_NAME);
+  let metadata_store_path = content_path.join(constants::METADATA_STORE_NAME);
+
+  // nothing was merged, the merged bundle of the previous merge is outdated
+  if files.is_empty() {
+    for path in &[bundle_path, metadata_store_path] {
+      if path.exists() {
+        fs::remove_file(path)?;
+      }
+    }
+
+    return Ok(report);
+  }
+
+  fs::create_dir_all(&content_path)?;
+
+  let entries = write_bundle(&bundle_path, &files)?;
+  write_metadata_store(
+    &metadata_store_path,
+    constants::MODLIST_MERGED_BUNDLE_NAME,
+    &entries,
+  )?;
+
+  modlist.link_own_merge()?;
+
+  Ok(report)
+}
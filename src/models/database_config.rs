@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use toml;
+
+use crate::constants;
+use crate::models::modlist::ModList;
+
+/// a named group of modlists, displayed on the home page
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ModListGroup {
+  pub name: String,
+
+  #[serde(default)]
+  pub description: String,
+
+  /// whether the group is folded on the home page
+  #[serde(default)]
+  pub collapsed: bool,
+}
+
+/// the configuration shared by every modlist of the database. It is stored in
+/// the modlist database directory, next to the modlists.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct DatabaseConfig {
+  /// the groups, in the order they're displayed from top to bottom
+  #[serde(default)]
+  pub groups: Vec<ModListGroup>,
+}
+
+impl DatabaseConfig {
+  pub fn path() -> PathBuf {
+    std::env::current_dir()
+      .unwrap()
+      .join(constants::MODLIST_DATABASE_PATH)
+      .join(constants::DATABASE_CONFIG_NAME)
+  }
+
+  /// reads the config from the disk, or returns an empty config if there is
+  /// no config file yet.
+  pub fn read_from_disk() -> std::io::Result<DatabaseConfig> {
+    let config_path = DatabaseConfig::path();
+
+    if !config_path.exists() {
+      return Ok(DatabaseConfig::default());
+    }
+
+    let text = fs::read_to_string(config_path)?;
+    let config: DatabaseConfig = toml::from_str(&text)?;
+
+    Ok(config)
+  }
+
+  pub fn write_to_disk(&self) -> Result<(), String> {
+    let content =
+      toml::to_string_pretty(&self).map_err(|_| "config serialization error".to_owned())?;
+
+    fs::write(DatabaseConfig::path(), content)
+      .map_err(|err| format!("disk write error {}", err))?;
+
+    Ok(())
+  }
+
+  pub fn has_group(&self, name: &str) -> bool {
+    self.groups.iter().any(|group| group.name == name)
+  }
+
+  pub fn get_group_mut(&mut self, name: &str) -> Option<&mut ModListGroup> {
+    self.groups.iter_mut().find(|group| group.name == name)
+  }
+
+  /// adds the group at the end of the list, does nothing if a group with the
+  /// same name already exists
+  pub fn add_group(&mut self, name: &str, description: &str) {
+    if self.has_group(name) {
+      return;
+    }
+
+    self.groups.push(ModListGroup {
+      name: name.to_owned(),
+      description: description.to_owned(),
+      collapsed: false,
+    });
+  }
+
+  pub fn remove_group(&mut self, name: &str) {
+    self.groups.retain(|group| group.name != name);
+  }
+
+  /// move the group higher in the list, which means at a lower index
+  pub fn move_group_up(&mut self, name: &str) {
+    let some_index = self.groups.iter().position(|group| group.name == name);
+
+    if let Some(index) = some_index {
+      if index > 0 {
+        self.groups.swap(index, index - 1);
+      }
+    }
+  }
+
+  /// move the group lower in the list, which means at a higher index
+  pub fn move_group_down(&mut self, name: &str) {
+    let some_index = self.groups.iter().position(|group| group.name == name);
+
+    if let Some(index) = some_index {
+      if index < self.groups.len() - 1 {
+        self.groups.swap(index, index + 1);
+      }
+    }
+  }
+
+  /// converts the numeric visibility levels the modlists used before groups
+  /// existed into named groups. The groups are created from the highest level
+  /// to the lowest, the same order the home page used to display them.
+  pub fn migrate_visibility_levels() -> std::io::Result<()> {
+    let mut config = DatabaseConfig::read_from_disk()?;
+    let mut levels = Vec::new();
+
+    for mut modlist in ModList::get_all() {
+      let some_level = modlist.read_legacy_visibility()?;
+
+      if let Some(level) = some_level {
+        // reading the metadata already converts the level into a group name,
+        // writing it back is what removes the level from the config file.
+        modlist.read_metadata_from_disk()?;
+        modlist
+          .write_metadata_to_disk()
+          .map_err(std::io::Error::other)?;
+
+        if !levels.contains(&level) {
+          levels.push(level);
+        }
+      }
+    }
+
+    if levels.is_empty() {
+      return Ok(());
+    }
+
+    levels.sort();
+    levels.reverse();
+
+    for level in levels {
+      if let Some(name) = ModList::visibility_group_name(level) {
+        config.add_group(
+          &name,
+          &format!("migrated from the visibility level {}", level),
+        );
+      }
+    }
+
+    config.write_to_disk().map_err(std::io::Error::other)?;
+
+    Ok(())
+  }
+}
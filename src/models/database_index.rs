@@ -0,0 +1,667 @@
+use rusqlite::{params, Connection, NO_PARAMS};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::constants;
+use crate::models::modlist::ModList;
+use crate::utils::hash::{hash_bytes, hash_file};
+
+/// the files larger than this are indexed without their hash, hashing the
+/// multi-gigabyte bundles of the vanilla modlist would make the first refresh
+/// take minutes for no benefit.
+pub const MAX_HASHED_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntryCategory {
+  Mod,
+  Dlc,
+  Menu,
+  Content,
+  Bundle,
+  Save,
+}
+
+impl EntryCategory {
+  pub fn all() -> [EntryCategory; 6] {
+    [
+      EntryCategory::Mod,
+      EntryCategory::Dlc,
+      EntryCategory::Menu,
+      EntryCategory::Content,
+      EntryCategory::Bundle,
+      EntryCategory::Save,
+    ]
+  }
+
+  /// the name of the modlist folder the category is stored in
+  pub fn folder_name(&self) -> &'static str {
+    match self {
+      EntryCategory::Mod => "mods",
+      EntryCategory::Dlc => "dlcs",
+      EntryCategory::Menu => "menus",
+      EntryCategory::Content => "content",
+      EntryCategory::Bundle => "bundles",
+      EntryCategory::Save => "saves",
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      EntryCategory::Mod => "mod",
+      EntryCategory::Dlc => "dlc",
+      EntryCategory::Menu => "menu",
+      EntryCategory::Content => "content",
+      EntryCategory::Bundle => "bundle",
+      EntryCategory::Save => "save",
+    }
+  }
+
+  pub fn from_str(category: &str) -> Option<EntryCategory> {
+    EntryCategory::all()
+      .iter()
+      .find(|c| c.as_str() == category)
+      .cloned()
+  }
+}
+
+/// a direct child of one of the modlist folders, for example a mod
+#[derive(Clone, Debug)]
+pub struct IndexedEntry {
+  pub modlist: String,
+  pub category: EntryCategory,
+  pub name: String,
+}
+
+/// a file of a modlist. The files directly in the category folders, like the
+/// menus, are indexed with an empty path.
+#[derive(Clone, Debug)]
+pub struct IndexedFile {
+  pub modlist: String,
+  pub category: EntryCategory,
+
+  /// the name of the entry the file is in
+  pub entry: String,
+
+  /// the path of the file relative to the entry, with `/` as the separator
+  pub path: String,
+
+  pub size: u64,
+
+  /// the modification time, in seconds since the unix epoch
+  pub mtime: i64,
+
+  /// the sha1 of the content, `None` for the files larger than
+  /// `MAX_HASHED_FILE_SIZE`
+  pub hash: Option<String>,
+}
+
+impl IndexedFile {
+  /// the path of the file in the modlist it is from
+  pub fn disk_path(&self) -> PathBuf {
+    let path = std::env::current_dir()
+      .unwrap()
+      .join(constants::MODLIST_DATABASE_PATH)
+      .join(&self.modlist)
+      .join(self.category.folder_name())
+      .join(&self.entry);
+
+    self
+      .path
+      .split('/')
+      .filter(|component| !component.is_empty())
+      .fold(path, |path, component| path.join(component))
+  }
+}
+
+/// an SQLite database stored next to the modlists that indexes the entries and
+/// files of every modlist. The symlinks of the loaded imports are not indexed,
+/// only the files a modlist really contains.
+pub struct DatabaseIndex {
+  connection: Connection,
+}
+
+fn to_io_error(error: rusqlite::Error) -> std::io::Error {
+  std::io::Error::other(error.to_string())
+}
+
+fn to_timestamp(time: SystemTime) -> i64 {
+  time
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs() as i64)
+    .unwrap_or(0)
+}
+
+/// the direct children of the folder that are not symlinks, in other words the
+/// children that were not loaded from an import.
+pub fn get_local_children(folder: &PathBuf) -> Vec<(PathBuf, String)> {
+  let children = match fs::read_dir(folder) {
+    Ok(children) => children,
+    Err(_) => return Vec::new(),
+  };
+
+  let mut output = children
+    .filter_map(|child| child.ok())
+    .filter(|child| {
+      child
+        .path()
+        .symlink_metadata()
+        .map(|metadata| !metadata.file_type().is_symlink())
+        .unwrap_or(false)
+    })
+    .filter_map(|child| {
+      let name = child.file_name().into_string().ok()?;
+
+      Some((child.path(), name))
+    })
+    .collect::<Vec<_>>();
+
+  output.sort_by(|a, b| a.1.cmp(&b.1));
+
+  output
+}
+
+/// pushes the path of every file in the directory and its children in the
+/// output, relative to the first directory.
+pub fn walk_files(directory: &PathBuf, prefix: &str, output: &mut Vec<(PathBuf, String)>) {
+  for (child, name) in get_local_children(directory) {
+    let path = if prefix.is_empty() {
+      name
+    } else {
+      format!("{}/{}", prefix, name)
+    };
+
+    if child.is_dir() {
+      walk_files(&child, &path, output);
+    } else {
+      output.push((child, path));
+    }
+  }
+}
+
+/// pushes the modification time of the directory and of every directory in
+/// it. Only the directories are read, their files are not.
+fn push_directory_stamps(directory: &PathBuf, prefix: &str, output: &mut String) {
+  let modified = fs::metadata(directory)
+    .and_then(|metadata| metadata.modified())
+    .map(to_timestamp)
+    .unwrap_or(0);
+
+  output.push_str(&format!("{}:{}\n", prefix, modified));
+
+  let children = match fs::read_dir(directory) {
+    Ok(children) => children,
+    Err(_) => return,
+  };
+
+  // the type of a directory entry doesn't follow symlinks and usually doesn't
+  // need a stat
+  let mut directories = children
+    .filter_map(|child| child.ok())
+    .filter(|child| child.file_type().map(|t| t.is_dir()).unwrap_or(false))
+    .map(|child| {
+      (
+        child.path(),
+        child.file_name().to_string_lossy().to_string(),
+      )
+    })
+    .collect::<Vec<_>>();
+
+  directories.sort_by(|a, b| a.1.cmp(&b.1));
+
+  for (child, name) in directories {
+    push_directory_stamps(&child, &format!("{}/{}", prefix, name), output);
+  }
+}
+
+/// a summary of the modification times of the directories of the modlist, and
+/// of the size and modification time of the files directly in its folders like
+/// the menus. Adding, removing or renaming a file anywhere changes it, which is
+/// when the modlist is indexed again. A file edited in place deep in a mod
+/// doesn't, the refresh must be forced then.
+fn get_fingerprint(modlist: &ModList) -> String {
+  let mut fingerprint = String::new();
+
+  for category in EntryCategory::all().iter() {
+    let folder = modlist.path().join(category.folder_name());
+    let prefix = category.as_str();
+
+    let modified = fs::metadata(&folder)
+      .and_then(|metadata| metadata.modified())
+      .map(to_timestamp)
+      .unwrap_or(0);
+
+    fingerprint.push_str(&format!("{}:{}\n", prefix, modified));
+
+    for (child, name) in get_local_children(&folder) {
+      if child.is_dir() {
+        push_directory_stamps(&child, &format!("{}/{}", prefix, name), &mut fingerprint);
+        continue;
+      }
+
+      let (size, modified) = fs::metadata(&child)
+        .map(|metadata| {
+          let modified = metadata.modified().map(to_timestamp).unwrap_or(0);
+
+          (metadata.len(), modified)
+        })
+        .unwrap_or((0, 0));
+
+      fingerprint.push_str(&format!("{}/{}:{}:{}\n", prefix, name, size, modified));
+    }
+  }
+
+  hash_bytes(fingerprint.as_bytes())
+}
+
+/// returns the modlists whose entries end up in the modlist, in the order they
+/// are loaded: the modlist itself then its imports, depth first.
+pub fn get_load_order(modlist: &ModList, modlists: &[ModList]) -> Vec<String> {
+  fn visit(name: &str, modlists: &[ModList], output: &mut Vec<String>) {
+    if output.iter().any(|visited| visited == name) {
+      return;
+    }
+
+    output.push(name.to_owned());
+
+    if let Some(modlist) = modlists.iter().find(|modlist| modlist.name == name) {
+      for import in &modlist.imported_modlists {
+        visit(import, modlists, output);
+      }
+    }
+  }
+
+  let mut output = Vec::new();
+  visit(&modlist.name, modlists, &mut output);
+
+  output
+}
+
+/// returns the entries that end up in the modlist once its imports are loaded,
+/// in load order. An entry from an import is ignored when an entry with the
+/// same name was already loaded, the same way the symlinks are when loading
+/// imports, and the disabled entries of the imports are not imported.
+pub fn get_effective_entries<'a>(
+  modlist: &ModList, modlists: &[ModList], entries: &'a [IndexedEntry],
+) -> Vec<&'a IndexedEntry> {
+  let mut loaded = HashSet::new();
+  let mut output = Vec::new();
+
+  for source in get_load_order(modlist, modlists) {
+    let is_local = source == modlist.name;
+
+    for entry in entries.iter().filter(|entry| entry.modlist == source) {
+      if !is_local && entry.name.starts_with('~') {
+        continue;
+      }
+
+      if loaded.insert((entry.category, entry.name.as_str())) {
+        output.push(entry);
+      }
+    }
+  }
+
+  output
+}
+
+/// the entries that end up in a modlist, with the index to read their files
+/// from.
+pub struct EffectiveEntries {
+  /// the modlist, with its metadata read from the disk
+  pub modlist: ModList,
+
+  /// the index, refreshed
+  pub index: DatabaseIndex,
+
+  /// see [`get_effective_entries`]
+  pub entries: Vec<IndexedEntry>,
+}
+
+/// reads the metadata of every modlist, refreshes the index then returns the
+/// entries that end up in the modlist once its imports are loaded.
+pub fn open_effective_entries(modlist_name: &str) -> std::io::Result<EffectiveEntries> {
+  let mut modlists = ModList::get_all();
+
+  for modlist in &mut modlists {
+    modlist.read_metadata_from_disk()?;
+  }
+
+  let modlist = modlists
+    .iter()
+    .find(|modlist| modlist.name == modlist_name)
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such modlist"))?;
+
+  let mut index = DatabaseIndex::open()?;
+  index.refresh(&modlists, false)?;
+
+  let all_entries = index.get_all_entries()?;
+  let entries = get_effective_entries(modlist, &modlists, &all_entries)
+    .into_iter()
+    .cloned()
+    .collect();
+
+  Ok(EffectiveEntries {
+    modlist: modlist.clone(),
+    index,
+    entries,
+  })
+}
+
+impl DatabaseIndex {
+  pub fn path() -> PathBuf {
+    std::env::current_dir()
+      .unwrap()
+      .join(constants::MODLIST_DATABASE_PATH)
+      .join(constants::DATABASE_INDEX_NAME)
+  }
+
+  /// opens the index and creates its tables if they don't exist yet
+  pub fn open() -> std::io::Result<DatabaseIndex> {
+    let connection = Connection::open(DatabaseIndex::path()).map_err(to_io_error)?;
+
+    // the pages can refresh the index at the same time
+    connection
+      .busy_timeout(Duration::from_secs(30))
+      .map_err(to_io_error)?;
+
+    connection
+      .execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS modlists (
+          name TEXT PRIMARY KEY NOT NULL,
+          fingerprint TEXT NOT NULL,
+          indexed_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS entries (
+          modlist TEXT NOT NULL,
+          category TEXT NOT NULL,
+          name TEXT NOT NULL,
+          PRIMARY KEY (modlist, category, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS files (
+          modlist TEXT NOT NULL,
+          category TEXT NOT NULL,
+          entry TEXT NOT NULL,
+          path TEXT NOT NULL,
+          size INTEGER NOT NULL,
+          mtime INTEGER NOT NULL,
+          hash TEXT,
+          PRIMARY KEY (modlist, category, entry, path)
+        );
+
+        CREATE INDEX IF NOT EXISTS files_hash ON files (hash);
+        ",
+      )
+      .map_err(to_io_error)?;
+
+    Ok(DatabaseIndex { connection })
+  }
+
+  /// brings the index up to date with the modlists. Only the modlists whose
+  /// fingerprint changed are walked again, unless `force` is set. And when a
+  /// modlist is walked, only the files whose size or modification time changed
+  /// are hashed again.
+  pub fn refresh(&mut self, modlists: &[ModList], force: bool) -> std::io::Result<()> {
+    let mut stmt = self
+      .connection
+      .prepare("SELECT name, fingerprint FROM modlists")
+      .map_err(to_io_error)?;
+
+    let fingerprints = stmt
+      .query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?)))
+      .map_err(to_io_error)?
+      .filter_map(Result::ok)
+      .collect::<HashMap<String, String>>();
+
+    drop(stmt);
+
+    // the modlists that were deleted or renamed
+    for name in fingerprints.keys() {
+      if !modlists.iter().any(|modlist| &modlist.name == name) {
+        self.remove_modlist(name)?;
+      }
+    }
+
+    for modlist in modlists {
+      let fingerprint = get_fingerprint(modlist);
+
+      if !force && fingerprints.get(&modlist.name) == Some(&fingerprint) {
+        continue;
+      }
+
+      self.refresh_modlist(modlist, &fingerprint)?;
+    }
+
+    Ok(())
+  }
+
+  fn remove_modlist(&mut self, name: &str) -> std::io::Result<()> {
+    let transaction = self.connection.transaction().map_err(to_io_error)?;
+
+    transaction
+      .execute("DELETE FROM modlists WHERE name = ?", params![name])
+      .map_err(to_io_error)?;
+    transaction
+      .execute("DELETE FROM entries WHERE modlist = ?", params![name])
+      .map_err(to_io_error)?;
+    transaction
+      .execute("DELETE FROM files WHERE modlist = ?", params![name])
+      .map_err(to_io_error)?;
+
+    transaction.commit().map_err(to_io_error)
+  }
+
+  fn refresh_modlist(&mut self, modlist: &ModList, fingerprint: &str) -> std::io::Result<()> {
+    println!("indexing modlist {}", modlist.name);
+
+    // the files already in the index, so the ones that didn't change are not
+    // hashed again.
+    let previous_files = self
+      .get_files(&modlist.name)?
+      .into_iter()
+      .map(|file| ((file.category, file.entry.clone(), file.path.clone()), file))
+      .collect::<HashMap<_, _>>();
+
+    let mut entries = Vec::new();
+    let mut files = Vec::new();
+
+    for category in EntryCategory::all().iter() {
+      let folder = modlist.path().join(category.folder_name());
+
+      for (child, name) in get_local_children(&folder) {
+        let mut child_files = Vec::new();
+
+        if child.is_dir() {
+          walk_files(&child, "", &mut child_files);
+        } else {
+          child_files.push((child.clone(), String::new()));
+        }
+
+        for (file_path, path) in child_files {
+          let metadata = match fs::metadata(&file_path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+          };
+
+          let size = metadata.len();
+          let mtime = metadata.modified().map(to_timestamp).unwrap_or(0);
+          let key = (*category, name.clone(), path.clone());
+
+          let hash = match previous_files.get(&key) {
+            Some(previous) if previous.size == size && previous.mtime == mtime => {
+              previous.hash.clone()
+            }
+            _ if size <= MAX_HASHED_FILE_SIZE => hash_file(&file_path).ok(),
+            _ => None,
+          };
+
+          files.push(IndexedFile {
+            modlist: modlist.name.clone(),
+            category: *category,
+            entry: name.clone(),
+            path,
+            size,
+            mtime,
+            hash,
+          });
+        }
+
+        entries.push(IndexedEntry {
+          modlist: modlist.name.clone(),
+          category: *category,
+          name,
+        });
+      }
+    }
+
+    let transaction = self.connection.transaction().map_err(to_io_error)?;
+
+    transaction
+      .execute(
+        "DELETE FROM entries WHERE modlist = ?",
+        params![modlist.name],
+      )
+      .map_err(to_io_error)?;
+    transaction
+      .execute("DELETE FROM files WHERE modlist = ?", params![modlist.name])
+      .map_err(to_io_error)?;
+
+    for entry in &entries {
+      transaction
+        .execute(
+          "INSERT INTO entries (modlist, category, name) VALUES (?, ?, ?)",
+          params![entry.modlist, entry.category.as_str(), entry.name],
+        )
+        .map_err(to_io_error)?;
+    }
+
+    for file in &files {
+      transaction
+        .execute(
+          "INSERT INTO files (modlist, category, entry, path, size, mtime, hash)
+          VALUES (?, ?, ?, ?, ?, ?, ?)",
+          params![
+            file.modlist,
+            file.category.as_str(),
+            file.entry,
+            file.path,
+            file.size as i64,
+            file.mtime,
+            file.hash
+          ],
+        )
+        .map_err(to_io_error)?;
+    }
+
+    transaction
+      .execute(
+        "INSERT OR REPLACE INTO modlists (name, fingerprint, indexed_at) VALUES (?, ?, ?)",
+        params![modlist.name, fingerprint, to_timestamp(SystemTime::now())],
+      )
+      .map_err(to_io_error)?;
+
+    transaction.commit().map_err(to_io_error)
+  }
+
+  /// every entry of every modlist, ordered by modlist then category then name
+  pub fn get_all_entries(&self) -> std::io::Result<Vec<IndexedEntry>> {
+    let mut stmt = self
+      .connection
+      .prepare("SELECT modlist, category, name FROM entries ORDER BY modlist, category, name")
+      .map_err(to_io_error)?;
+
+    let entries = stmt
+      .query_map(NO_PARAMS, |row| {
+        let category: String = row.get(1)?;
+
+        Ok(IndexedEntry {
+          modlist: row.get(0)?,
+          category: EntryCategory::from_str(&category).unwrap_or(EntryCategory::Mod),
+          name: row.get(2)?,
+        })
+      })
+      .map_err(to_io_error)?
+      .filter_map(Result::ok)
+      .collect();
+
+    Ok(entries)
+  }
+
+  fn row_to_file(row: &rusqlite::Row) -> rusqlite::Result<IndexedFile> {
+    let category: String = row.get(1)?;
+    let size: i64 = row.get(4)?;
+
+    Ok(IndexedFile {
+      modlist: row.get(0)?,
+      category: EntryCategory::from_str(&category).unwrap_or(EntryCategory::Mod),
+      entry: row.get(2)?,
+      path: row.get(3)?,
+      size: size as u64,
+      mtime: row.get(5)?,
+      hash: row.get(6)?,
+    })
+  }
+
+  /// the files of the modlist, without the files of its imports
+  pub fn get_files(&self, modlist_name: &str) -> std::io::Result<Vec<IndexedFile>> {
+    let mut stmt = self
+      .connection
+      .prepare(
+        "SELECT modlist, category, entry, path, size, mtime, hash FROM files
+        WHERE modlist = ? ORDER BY category, entry, path",
+      )
+      .map_err(to_io_error)?;
+
+    let files = stmt
+      .query_map(params![modlist_name], DatabaseIndex::row_to_file)
+      .map_err(to_io_error)?
+      .filter_map(Result::ok)
+      .collect();
+
+    Ok(files)
+  }
+
+  /// the files, in every modlist, whose path relative to the category folder
+  /// contains the query. The comparison ignores the case.
+  pub fn find_files(&self, query: &str) -> std::io::Result<Vec<IndexedFile>> {
+    let mut stmt = self
+      .connection
+      .prepare(
+        "SELECT modlist, category, entry, path, size, mtime, hash FROM files
+        WHERE instr(lower(entry || '/' || path), lower(?)) > 0
+        ORDER BY modlist, category, entry, path",
+      )
+      .map_err(to_io_error)?;
+
+    let files = stmt
+      .query_map(params![query], DatabaseIndex::row_to_file)
+      .map_err(to_io_error)?
+      .filter_map(Result::ok)
+      .collect();
+
+    Ok(files)
+  }
+
+  /// the size in bytes of every file of every modlist, without their imports
+  pub fn get_modlist_sizes(&self) -> std::io::Result<HashMap<String, u64>> {
+    let mut stmt = self
+      .connection
+      .prepare("SELECT modlist, SUM(size) FROM files GROUP BY modlist")
+      .map_err(to_io_error)?;
+
+    let sizes = stmt
+      .query_map(NO_PARAMS, |row| {
+        let size: i64 = row.get(1)?;
+
+        Ok((row.get(0)?, size as u64))
+      })
+      .map_err(to_io_error)?
+      .filter_map(Result::ok)
+      .collect();
+
+    Ok(sizes)
+  }
+}
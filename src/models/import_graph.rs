@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::database_index::DatabaseIndex;
+use crate::models::modlist::ModList;
+
+#[derive(Serialize, Debug)]
+pub struct ImportGraphNode {
+  pub name: String,
+  pub group: Option<String>,
+  pub installable: bool,
+
+  /// size in bytes of the files of the modlist, without its imports
+  pub size: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportGraphEdge {
+  /// the modlist that imports `to`
+  pub from: String,
+  pub to: String,
+
+  /// the position of the import in the load order of `from`, starting at 0
+  pub order: usize,
+
+  /// `false` when `to` is not a modlist of the database
+  pub valid: bool,
+}
+
+/// the imports of every modlist in the database
+#[derive(Serialize, Debug)]
+pub struct ImportGraph {
+  pub nodes: Vec<ImportGraphNode>,
+  pub edges: Vec<ImportGraphEdge>,
+}
+
+impl ImportGraph {
+  pub fn from_database() -> std::io::Result<ImportGraph> {
+    let mut modlists = ModList::get_all();
+    modlists.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for modlist in &mut modlists {
+      modlist.read_metadata_from_disk()?;
+    }
+
+    // the sizes come from the index rather than walking every modlist each
+    // time the graph is rendered.
+    let mut index = DatabaseIndex::open()?;
+    index.refresh(&modlists, false)?;
+    let sizes = index.get_modlist_sizes()?;
+
+    let names: HashSet<&str> = modlists.iter().map(|m| m.name.as_str()).collect();
+
+    let mut edges = Vec::new();
+    for modlist in &modlists {
+      for (order, import) in modlist.imported_modlists.iter().enumerate() {
+        edges.push(ImportGraphEdge {
+          from: modlist.name.clone(),
+          to: import.clone(),
+          order,
+          valid: names.contains(import.as_str()),
+        });
+      }
+    }
+
+    let nodes = modlists
+      .iter()
+      .map(|modlist| ImportGraphNode {
+        name: modlist.name.clone(),
+        group: modlist.group.clone(),
+        installable: modlist.is_installable(),
+        size: sizes.get(&modlist.name).cloned().unwrap_or(0),
+      })
+      .collect();
+
+    Ok(ImportGraph { nodes, edges })
+  }
+
+  pub fn imports_of(&self, name: &str) -> Vec<&ImportGraphEdge> {
+    self.edges.iter().filter(|edge| edge.from == name).collect()
+  }
+
+  pub fn importers_of(&self, name: &str) -> Vec<&ImportGraphEdge> {
+    self.edges.iter().filter(|edge| edge.to == name).collect()
+  }
+
+  /// the modlists that import nothing and that no other modlist imports, they
+  /// can't be installed and are never used.
+  pub fn get_orphans(&self) -> Vec<&ImportGraphNode> {
+    self
+      .nodes
+      .iter()
+      .filter(|node| node.name != "vanilla")
+      .filter(|node| self.imports_of(&node.name).is_empty())
+      .filter(|node| self.importers_of(&node.name).is_empty())
+      .collect()
+  }
+
+  /// the modlists that import other modlists but not vanilla, and that no other
+  /// modlist imports. They look like modlists meant to be installed that forgot
+  /// to import vanilla.
+  pub fn get_missing_vanilla(&self) -> Vec<&ImportGraphNode> {
+    self
+      .nodes
+      .iter()
+      .filter(|node| !node.installable)
+      .filter(|node| !self.imports_of(&node.name).is_empty())
+      .filter(|node| self.importers_of(&node.name).is_empty())
+      .collect()
+  }
+
+  /// the imports that point to modlists that do not exist
+  pub fn get_invalid_edges(&self) -> Vec<&ImportGraphEdge> {
+    self.edges.iter().filter(|edge| !edge.valid).collect()
+  }
+
+  /// returns every group of modlists that import each other in a loop, using
+  /// Tarjan's strongly connected components algorithm.
+  pub fn get_cycles(&self) -> Vec<Vec<String>> {
+    struct State<'a> {
+      graph: &'a ImportGraph,
+      index: usize,
+      indices: HashMap<&'a str, usize>,
+      lowlinks: HashMap<&'a str, usize>,
+      stack: Vec<&'a str>,
+      on_stack: HashSet<&'a str>,
+      cycles: Vec<Vec<String>>,
+    }
+
+    fn visit<'a>(state: &mut State<'a>, name: &'a str) {
+      state.indices.insert(name, state.index);
+      state.lowlinks.insert(name, state.index);
+      state.index += 1;
+      state.stack.push(name);
+      state.on_stack.insert(name);
+
+      let graph = state.graph;
+      for edge in graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from == name && edge.valid)
+      {
+        let to = edge.to.as_str();
+
+        if !state.indices.contains_key(to) {
+          visit(state, to);
+
+          let lowlink = state.lowlinks[name].min(state.lowlinks[to]);
+          state.lowlinks.insert(name, lowlink);
+        } else if state.on_stack.contains(to) {
+          let lowlink = state.lowlinks[name].min(state.indices[to]);
+          state.lowlinks.insert(name, lowlink);
+        }
+      }
+
+      if state.lowlinks[name] == state.indices[name] {
+        let mut component = Vec::new();
+
+        while let Some(member) = state.stack.pop() {
+          state.on_stack.remove(member);
+          component.push(member.to_owned());
+
+          if member == name {
+            break;
+          }
+        }
+
+        let is_self_import = graph
+          .edges
+          .iter()
+          .any(|edge| edge.from == name && edge.to == name);
+
+        if component.len() > 1 || is_self_import {
+          component.reverse();
+          state.cycles.push(component);
+        }
+      }
+    }
+
+    let mut state = State {
+      graph: self,
+      index: 0,
+      indices: HashMap::new(),
+      lowlinks: HashMap::new(),
+      stack: Vec::new(),
+      on_stack: HashSet::new(),
+      cycles: Vec::new(),
+    };
+
+    for node in &self.nodes {
+      if !state.indices.contains_key(node.name.as_str()) {
+        visit(&mut state, &node.name);
+      }
+    }
+
+    state.cycles
+  }
+
+  /// the length of the longest import chain starting from each modlist. vanilla
+  /// and the modlists without imports are at level 0, a modlist importing only
+  /// vanilla is at level 1, etc...
+  pub fn get_levels(&self) -> HashMap<&str, usize> {
+    fn level<'a>(
+      graph: &'a ImportGraph, name: &'a str, levels: &mut HashMap<&'a str, usize>,
+      visiting: &mut HashSet<&'a str>,
+    ) -> usize {
+      if let Some(level) = levels.get(name) {
+        return *level;
+      }
+
+      // a cyclic import, stop there
+      if !visiting.insert(name) {
+        return 0;
+      }
+
+      let value = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from == name && edge.valid)
+        .map(|edge| level(graph, &edge.to, levels, visiting) + 1)
+        .max()
+        .unwrap_or(0);
+
+      visiting.remove(name);
+      levels.insert(name, value);
+
+      value
+    }
+
+    let mut levels = HashMap::new();
+    let mut visiting = HashSet::new();
+
+    for node in &self.nodes {
+      level(self, &node.name, &mut levels, &mut visiting);
+    }
+
+    levels
+  }
+
+  /// serializes the graph in the Graphviz DOT format
+  pub fn to_dot(&self) -> String {
+    fn escape(text: &str) -> String {
+      text.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+
+    let mut output = String::from("digraph imports {\n  rankdir=LR;\n  node [shape=box];\n\n");
+
+    for node in &self.nodes {
+      output.push_str(&format!(
+        "  \"{}\" [label=\"{}\\n{}\", modlist_group=\"{}\", bytes={}{}];\n",
+        escape(&node.name),
+        escape(&node.name),
+        crate::utils::format_size(node.size),
+        escape(node.group.as_deref().unwrap_or("")),
+        node.size,
+        if node.installable { ", style=bold" } else { "" }
+      ));
+    }
+
+    output.push('\n');
+
+    for edge in &self.edges {
+      output.push_str(&format!(
+        "  \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+        escape(&edge.from),
+        escape(&edge.to),
+        edge.order + 1,
+        if edge.valid { "" } else { ", style=dashed" }
+      ));
+    }
+
+    output.push_str("}\n");
+
+    output
+  }
+}
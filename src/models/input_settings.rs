@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use toml;
+
+use crate::constants;
+use crate::models::modlist::ModList;
+use crate::models::script_merge::read_script;
+use crate::models::settings_file::SettingsFile;
+use crate::utils::symlinks::write_replacing_symlink;
+
+/// the bindings the last input merge inserted in the input.settings file of
+/// the modlist, by mod then by context. They are stored in the modlist folder
+/// so the next merge can take them out again.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct InputBindings {
+  #[serde(default)]
+  pub mods: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl InputBindings {
+  pub fn path(modlist: &ModList) -> PathBuf {
+    modlist.path().join(constants::MODLIST_INPUT_BINDINGS_NAME)
+  }
+
+  /// reads the bindings from the disk, or returns no binding if the modlist
+  /// input was never merged.
+  pub fn read_from_disk(modlist: &ModList) -> std::io::Result<InputBindings> {
+    let path = InputBindings::path(modlist);
+
+    if !path.exists() {
+      return Ok(InputBindings::default());
+    }
+
+    let text = fs::read_to_string(path)?;
+    let bindings: InputBindings = toml::from_str(&text)?;
+
+    Ok(bindings)
+  }
+
+  pub fn write_to_disk(&self, modlist: &ModList) -> std::io::Result<()> {
+    let content = toml::to_string_pretty(&self).map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "bindings serialization error",
+      )
+    })?;
+
+    fs::write(InputBindings::path(modlist), content)
+  }
+
+  pub fn count(&self) -> usize {
+    self
+      .mods
+      .values()
+      .flat_map(|contexts| contexts.values())
+      .map(|lines| lines.len())
+      .sum()
+  }
+}
+
+pub struct InputMergeReport {
+  /// the mods of the modlist with an input snippet, imported mods included
+  pub mods: Vec<String>,
+
+  /// the number of bindings inserted, and removed, by the merge
+  pub inserted: usize,
+  pub removed: usize,
+}
+
+/// inserts the input snippets of the mods of the modlist in the contexts of its
+/// `saves/input.settings` file. The bindings of the previous merge are taken
+/// out first so the bindings of the mods that are gone go away, and the lines
+/// the file already has are not added twice.
+pub fn merge_input_settings(modlist: &ModList) -> std::io::Result<InputMergeReport> {
+  let settings_path = modlist.saves_path().join(constants::INPUT_SETTINGS_NAME);
+
+  // without the file of the game there is nothing to merge in, a file made of
+  // the snippets alone would hide the one of the imported modlists.
+  if !settings_path.exists() {
+    return Ok(InputMergeReport {
+      mods: Vec::new(),
+      inserted: 0,
+      removed: 0,
+    });
+  }
+
+  let current = read_script(&settings_path)?;
+
+  let mut settings = SettingsFile::parse(&current);
+  let previous = InputBindings::read_from_disk(modlist)?;
+  let mut removed = 0;
+
+  for contexts in previous.mods.values() {
+    for (context, lines) in contexts {
+      for line in lines {
+        removed += settings.remove(context, line) as usize;
+      }
+    }
+  }
+
+  // the mods of the folder, the loaded imports included, in the load order
+  let mut mod_names = fs::read_dir(modlist.mods_path())?
+    .filter_map(|child| child.ok())
+    .map(|child| child.file_name().to_string_lossy().to_string())
+    .filter(|name| !name.starts_with('~'))
+    .collect::<Vec<_>>();
+
+  mod_names.sort_by_key(|name| name.to_lowercase());
+
+  let mut bindings = InputBindings::default();
+  let mut mods = Vec::new();
+  let mut inserted = 0;
+
+  for mod_name in mod_names {
+    let snippet_path = modlist
+      .mods_path()
+      .join(&mod_name)
+      .join(constants::MOD_INPUT_SNIPPET_NAME);
+
+    if !snippet_path.is_file() {
+      continue;
+    }
+
+    let snippet = SettingsFile::parse(&read_script(&snippet_path)?);
+    let mut contexts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (context, lines) in &snippet.sections {
+      // a binding outside of a context can't be placed
+      if context.is_empty() {
+        continue;
+      }
+
+      for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        if settings.insert(context, line) {
+          contexts
+            .entry(context.clone())
+            .or_default()
+            .push(line.clone());
+
+          inserted += 1;
+        }
+      }
+    }
+
+    if !contexts.is_empty() {
+      bindings.mods.insert(mod_name.clone(), contexts);
+    }
+
+    mods.push(mod_name);
+  }
+
+  let content = settings.to_text();
+
+  // the file is only rewritten when bindings moved, the game reformats it
+  // anyway.
+  if (inserted > 0 || removed > 0) && content != current {
+    write_replacing_symlink(&settings_path, content)?;
+  }
+
+  if bindings.count() > 0 || previous.count() > 0 {
+    bindings.write_to_disk(modlist)?;
+  }
+
+  Ok(InputMergeReport {
+    mods,
+    inserted,
+    removed,
+  })
+}
@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use crate::models::database_index::{open_effective_entries, EffectiveEntries, EntryCategory};
+use crate::models::modlist::ModList;
+use crate::models::script_conflicts::is_mergedfiles;
+use crate::models::script_merge::read_script;
+
+/// how the game displays a variable of a menu, from its `displayType`
+#[derive(Clone, Debug)]
+pub enum MenuDisplayType {
+  /// `SLIDER;min;max;steps`
+  Slider {
+    min: f64,
+    max: f64,
+    steps: u32,
+  },
+  Toggle,
+
+  /// the ids of the options and their display names
+  Options(Vec<(String, String)>),
+
+  /// the separators between the variables, they have no value
+  Separator,
+
+  /// any other display type, the value is edited as text
+  Other(String),
+}
+
+impl MenuDisplayType {
+  fn parse(display_type: &str, var: roxmltree::Node) -> MenuDisplayType {
+    let mut parts = display_type.split(';').map(|part| part.trim());
+    let kind = parts.next().unwrap_or_default().to_uppercase();
+
+    match kind.as_str() {
+      "SLIDER" => {
+        let min = parts.next().and_then(|part| part.parse().ok());
+        let max = parts.next().and_then(|part| part.parse().ok());
+        let steps = parts.next().and_then(|part| part.parse().ok());
+
+        match (min, max, steps) {
+          (Some(min), Some(max), Some(steps)) => MenuDisplayType::Slider { min, max, steps },
+          _ => MenuDisplayType::Other(display_type.to_owned()),
+        }
+      }
+      "TOGGLE" => MenuDisplayType::Toggle,
+      "OPTIONS" => MenuDisplayType::Options(
+        var
+          .descendants()
+          .filter(|node| node.has_tag_name("Option"))
+          .filter_map(|option| {
+            let id = option.attribute("id")?;
+            let name = option.attribute("displayName").unwrap_or(id);
+
+            Some((id.to_owned(), name.to_owned()))
+          })
+          .collect(),
+      ),
+      "SUBTLE_SEPARATOR" | "SEPARATOR" => MenuDisplayType::Separator,
+      _ => MenuDisplayType::Other(display_type.to_owned()),
+    }
+  }
+
+  /// the value the game shows for the variable when the user.settings file
+  /// has none.
+  pub fn default_value(&self) -> String {
+    match self {
+      MenuDisplayType::Slider { min, .. } => min.to_string(),
+      MenuDisplayType::Toggle => String::from("false"),
+      MenuDisplayType::Options(options) => options
+        .first()
+        .map(|(id, _)| id.clone())
+        .unwrap_or_default(),
+      MenuDisplayType::Separator | MenuDisplayType::Other(_) => String::new(),
+    }
+  }
+}
+
+/// a variable of a group of a menu file
+#[derive(Clone, Debug)]
+pub struct MenuVar {
+  pub id: String,
+  pub display_name: String,
+  pub display_type: MenuDisplayType,
+}
+
+/// a group of options of a menu file, with its variables
+#[derive(Clone, Debug)]
+pub struct MenuGroup {
+  pub id: String,
+  pub display_name: String,
+  pub vars: Vec<MenuVar>,
+}
+
+/// a menu file of the effective menus of a modlist
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MenuRef {
+  pub name: String,
+
+  /// the modlist the menu comes from, it is the analyzed modlist itself when
+  /// the menu is not imported.
+  pub modlist: String,
+}
+
+/// an id more than one menu, or more than once the same menu, defines
+#[derive(Clone, Debug)]
+pub struct MenuCollision {
+  /// the id of the group, or the id of the group and of the variable separated
+  /// by a dot for the variables.
+  pub id: String,
+
+  /// the menus that define the id, once per definition
+  pub menus: Vec<MenuRef>,
+}
+
+pub struct MenuReport {
+  /// the number of menu files of the modlist
+  pub menus: usize,
+
+  /// the menus that could not be parsed, with the reason
+  pub malformed: Vec<(MenuRef, String)>,
+
+  pub duplicate_groups: Vec<MenuCollision>,
+  pub duplicate_vars: Vec<MenuCollision>,
+
+  /// the menus none of the mods of the modlist seems to use, the menus of the
+  /// game are never listed.
+  pub unused: Vec<MenuRef>,
+}
+
+/// parses the menu file and returns its groups, or why it is malformed
+pub fn parse_menu(path: &PathBuf) -> Result<Vec<MenuGroup>, String> {
+  let text = read_script(path).map_err(|error| error.to_string())?;
+  let document = roxmltree::Document::parse(&text).map_err(|error| error.to_string())?;
+
+  let root = document.root_element();
+  if !root.has_tag_name("UserConfig") {
+    return Err(format!(
+      "the root element is {} instead of UserConfig",
+      root.tag_name().name()
+    ));
+  }
+
+  let mut groups = Vec::new();
+
+  for group in root.children().filter(|node| node.has_tag_name("Group")) {
+    let id = group
+      .attribute("id")
+      .ok_or_else(|| format!("a group has no id, line {}", line_of(&document, group)))?;
+
+    let mut vars = Vec::new();
+
+    for var in group.descendants().filter(|node| node.has_tag_name("Var")) {
+      let var_id = var.attribute("id").ok_or_else(|| {
+        format!(
+          "a variable of {} has no id, line {}",
+          id,
+          line_of(&document, var)
+        )
+      })?;
+
+      vars.push(MenuVar {
+        id: var_id.to_owned(),
+        display_name: var.attribute("displayName").unwrap_or(var_id).to_owned(),
+        display_type: MenuDisplayType::parse(var.attribute("displayType").unwrap_or_default(), var),
+      });
+    }
+
+    groups.push(MenuGroup {
+      id: id.to_owned(),
+      display_name: group.attribute("displayName").unwrap_or(id).to_owned(),
+      vars,
+    });
+  }
+
+  Ok(groups)
+}
+
+fn line_of(document: &roxmltree::Document, node: roxmltree::Node) -> u32 {
+  document.text_pos_at(node.range().start).row
+}
+
+/// the ids defined more than once, compared without their case like the game
+/// does.
+fn find_collisions(definitions: Vec<(String, MenuRef)>) -> Vec<MenuCollision> {
+  let mut ids: BTreeMap<String, MenuCollision> = BTreeMap::new();
+
+  for (id, menu) in definitions {
+    ids
+      .entry(id.to_lowercase())
+      .or_insert_with(|| MenuCollision {
+        id,
+        menus: Vec::new(),
+      })
+      .menus
+      .push(menu);
+  }
+
+  ids
+    .into_values()
+    .filter(|collision| collision.menus.len() > 1)
+    .collect()
+}
+
+/// parses the menu files of the modlist, imported menus included, and lists the
+/// malformed ones, the ids defined more than once and the menus that seem
+/// unused. A menu is considered used when one of the scripts of the mods
+/// mentions one of its groups, or when a mod has a file with the same name.
+pub fn analyze(modlist_name: &str) -> std::io::Result<MenuReport> {
+  let EffectiveEntries {
+    index,
+    entries: effective_entries,
+    ..
+  } = open_effective_entries(modlist_name)?;
+
+  let mut menus = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Menu)
+    .filter(|entry| entry.name.to_lowercase().ends_with(".xml"))
+    .collect::<Vec<_>>();
+
+  menus.sort_by_key(|entry| entry.name.to_lowercase());
+
+  let mut malformed = Vec::new();
+  let mut group_definitions = Vec::new();
+  let mut var_definitions = Vec::new();
+
+  // the groups of every menu that could be unused
+  let mut mod_menus = Vec::new();
+
+  for entry in &menus {
+    let menu = MenuRef {
+      name: entry.name.clone(),
+      modlist: entry.modlist.clone(),
+    };
+
+    let path = ModList::get_by_name(&entry.modlist)
+      .map(|modlist| modlist.menus_path().join(&entry.name))
+      .unwrap_or_default();
+
+    let groups = match parse_menu(&path) {
+      Ok(groups) => groups,
+      Err(error) => {
+        malformed.push((menu, error));
+        continue;
+      }
+    };
+
+    for group in &groups {
+      group_definitions.push((group.id.clone(), menu.clone()));
+
+      for var in &group.vars {
+        var_definitions.push((format!("{}.{}", group.id, var.id), menu.clone()));
+      }
+    }
+
+    // the menus of the vanilla modlist are the ones of the game
+    if entry.modlist != "vanilla" {
+      mod_menus.push((menu, groups));
+    }
+  }
+
+  let mods = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~') && !is_mergedfiles(&entry.name))
+    .collect::<Vec<_>>();
+
+  let mod_names = mods
+    .iter()
+    .map(|entry| (entry.modlist.as_str(), entry.name.as_str()))
+    .collect::<HashSet<_>>();
+
+  let mut sources = mods
+    .iter()
+    .map(|entry| entry.modlist.as_str())
+    .collect::<Vec<_>>();
+
+  sources.sort();
+  sources.dedup();
+
+  // the ids of the groups, and the names of the files, nothing mentions yet
+  let mut unmentioned_groups = mod_menus
+    .iter()
+    .flat_map(|(_, groups)| groups.iter().map(|group| group.id.to_lowercase()))
+    .collect::<HashSet<_>>();
+
+  let mut mod_file_names = HashSet::new();
+
+  for source in sources {
+    for file in index.get_files(source)? {
+      if file.category != EntryCategory::Mod
+        || !mod_names.contains(&(file.modlist.as_str(), file.entry.as_str()))
+      {
+        continue;
+      }
+
+      let lowercase_path = file.path.to_lowercase();
+
+      if let Some(file_name) = lowercase_path.rsplit('/').next() {
+        mod_file_names.insert(file_name.to_owned());
+      }
+
+      if unmentioned_groups.is_empty() || !lowercase_path.ends_with(".ws") {
+        continue;
+      }
+
+      let script = match read_script(&file.disk_path()) {
+        Ok(script) => script.to_lowercase(),
+        Err(_) => continue,
+      };
+
+      unmentioned_groups.retain(|group| !script.contains(group.as_str()));
+    }
+  }
+
+  let unused = mod_menus
+    .into_iter()
+    .filter(|(menu, groups)| {
+      !mod_file_names.contains(&menu.name.to_lowercase())
+        && groups
+          .iter()
+          .all(|group| unmentioned_groups.contains(&group.id.to_lowercase()))
+    })
+    .map(|(menu, _)| menu)
+    .collect();
+
+  Ok(MenuReport {
+    menus: menus.len(),
+    malformed,
+    duplicate_groups: find_collisions(group_definitions),
+    duplicate_vars: find_collisions(var_definitions),
+    unused,
+  })
+}
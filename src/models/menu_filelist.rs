@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::constants;
+use crate::models::script_merge::read_script;
+use crate::utils::symlinks::write_replacing_symlink;
+
+/// the line that separates the lines written by hand from the generated ones.
+/// The game ignores it as it doesn't name an existing menu.
+const GENERATED_SECTION_MARKER: &str =
+  ";; the lines below are generated by the modlist manager, write yours above";
+
+/// the name of the menu a line of a file list refers to, the lines are in the
+/// `name.xml;` form.
+fn line_menu_name(line: &str) -> &str {
+  line.trim().trim_end_matches(';').trim()
+}
+
+/// splits the lines of the file list in the lines written by hand, the lines
+/// above the marker, and the generated ones. A list without the marker was
+/// never generated, its lines that name a menu of the folder are then the
+/// generated ones and the others are kept.
+fn split_filelist(content: &str, menus: &HashSet<String>) -> (Vec<String>, Vec<String>) {
+  let lines = content.lines().map(|line| line.trim_end());
+
+  if content
+    .lines()
+    .any(|line| line.trim() == GENERATED_SECTION_MARKER)
+  {
+    let mut protected = Vec::new();
+    let mut generated = Vec::new();
+    let mut is_generated = false;
+
+    for line in lines {
+      if line.trim() == GENERATED_SECTION_MARKER {
+        is_generated = true;
+      } else if is_generated {
+        if !line.is_empty() {
+          generated.push(line.to_owned());
+        }
+      } else {
+        protected.push(line.to_owned());
+      }
+    }
+
+    return (protected, generated);
+  }
+
+  lines
+    .filter(|line| !line_menu_name(line).is_empty())
+    .map(String::from)
+    .partition(|line| !menus.contains(&line_menu_name(line).to_lowercase()))
+}
+
+/// rewrites the file lists of the menus folder so they list every enabled menu
+/// of the folder, the imported ones included. The lines written by hand are
+/// kept at the top of the lists, the menus that were already listed keep their
+/// order and the new ones are added after them.
+pub fn update_filelists(menus_path: &Path) -> std::io::Result<()> {
+  let mut menus = Vec::new();
+
+  for child in fs::read_dir(menus_path)? {
+    let name = child?.file_name().to_string_lossy().to_string();
+
+    if name.ends_with(".xml") {
+      menus.push(name);
+    }
+  }
+
+  menus.sort_by_key(|name| name.to_lowercase());
+
+  let lowercase_menus = menus
+    .iter()
+    .map(|name| name.to_lowercase())
+    .collect::<HashSet<_>>();
+
+  for filelist_name in constants::MENU_FILELIST_NAMES {
+    let filelist_path = menus_path.join(filelist_name);
+
+    let current = read_script(&filelist_path).unwrap_or_default();
+
+    // the list may be a symlink to the list of an imported modlist, it is
+    // replaced by a file but its lines written by hand are kept.
+    let was_symlink = filelist_path
+      .symlink_metadata()
+      .map(|metadata| metadata.file_type().is_symlink())
+      .unwrap_or(false);
+    let (protected, generated) = split_filelist(&current, &lowercase_menus);
+
+    let mut listed = protected
+      .iter()
+      .map(|line| line_menu_name(line).to_lowercase())
+      .collect::<HashSet<_>>();
+
+    let mut content = String::new();
+
+    for line in &protected {
+      content.push_str(line);
+      content.push_str("\r\n");
+    }
+
+    content.push_str(GENERATED_SECTION_MARKER);
+    content.push_str("\r\n");
+
+    // the order of the menus matters to the game, the menus that are still in
+    // the folder stay where they were
+    for line in &generated {
+      let menu = line_menu_name(line).to_lowercase();
+
+      if lowercase_menus.contains(&menu) && listed.insert(menu) {
+        content.push_str(line);
+        content.push_str("\r\n");
+      }
+    }
+
+    for menu in &menus {
+      if listed.insert(menu.to_lowercase()) {
+        content.push_str(menu);
+        content.push_str(";\r\n");
+      }
+    }
+
+    if was_symlink || content != current {
+      write_replacing_symlink(&filelist_path, content)?;
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use crate::models::database_index::{open_effective_entries, EntryCategory};
+use crate::models::modlist::ModList;
+use crate::models::script_merge::read_script;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InventoryMergeKind {
+  Script,
+  Bundle,
+}
+
+impl InventoryMergeKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      InventoryMergeKind::Script => "script",
+      InventoryMergeKind::Bundle => "bundle",
+    }
+  }
+}
+
+/// a file the scriptmerger merged, from its merge inventory
+#[derive(Clone, Debug)]
+pub struct InventoryMerge {
+  pub kind: InventoryMergeKind,
+
+  /// the path of the merged file, relative to the mod folders for the scripts
+  /// and to the bundles for the bundle merges.
+  pub path: String,
+
+  /// the mod the merged file was written in
+  pub merged_mod: String,
+
+  /// the mods whose files were merged, in the inventory order
+  pub included_mods: Vec<String>,
+
+  /// the included mods that are not in the effective mods of the modlist
+  pub missing_mods: Vec<String>,
+}
+
+/// the content of the `MergeInventory.xml` file of a modlist, the file the
+/// scriptmerger keeps its merges in.
+#[derive(Clone, Debug, Default)]
+pub struct MergeInventory {
+  pub merges: Vec<InventoryMerge>,
+}
+
+impl MergeInventory {
+  pub fn count(&self, kind: InventoryMergeKind) -> usize {
+    self
+      .merges
+      .iter()
+      .filter(|merge| merge.kind == kind)
+      .count()
+  }
+
+  /// the merges that include a mod the modlist doesn't have anymore
+  pub fn stale_count(&self) -> usize {
+    self
+      .merges
+      .iter()
+      .filter(|merge| !merge.missing_mods.is_empty())
+      .count()
+  }
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, names: &[&str]) -> Option<&'a str> {
+  node
+    .children()
+    .find(|child| names.contains(&child.tag_name().name()))
+    .and_then(|child| child.text())
+    .map(|text| text.trim())
+}
+
+/// parses the merge inventory. The merges are `Merge` elements, the ones under
+/// a `BundleMerges` element, or named `BundleMerge`, are bundle merges.
+pub fn parse_merge_inventory(text: &str) -> Result<MergeInventory, String> {
+  let document = roxmltree::Document::parse(text).map_err(|error| error.to_string())?;
+
+  let root = document.root_element();
+  if !root.has_tag_name("MergeInventory") {
+    return Err(format!(
+      "the root element is {} instead of MergeInventory",
+      root.tag_name().name()
+    ));
+  }
+
+  let mut merges = Vec::new();
+
+  for node in root.descendants() {
+    let is_bundle = match node.tag_name().name() {
+      "BundleMerge" => true,
+      "Merge" => node
+        .ancestors()
+        .any(|ancestor| ancestor.has_tag_name("BundleMerges")),
+      _ => continue,
+    };
+
+    merges.push(InventoryMerge {
+      kind: if is_bundle {
+        InventoryMergeKind::Bundle
+      } else {
+        InventoryMergeKind::Script
+      },
+      path: child_text(node, &["RelPath", "RelativePath", "File"])
+        .unwrap_or_default()
+        .to_owned(),
+      merged_mod: child_text(node, &["MergedModName"])
+        .unwrap_or_default()
+        .to_owned(),
+      included_mods: node
+        .children()
+        .filter(|child| child.has_tag_name("IncludedMod"))
+        .filter_map(|child| child.text())
+        .map(|text| text.trim().to_owned())
+        .filter(|text| !text.is_empty())
+        .collect(),
+      missing_mods: Vec::new(),
+    });
+  }
+
+  Ok(MergeInventory { merges })
+}
+
+/// reads the merge inventory of the modlist and flags the included mods that
+/// are not in its effective mods, imported mods included.
+pub fn analyze(modlist_name: &str) -> std::io::Result<MergeInventory> {
+  let modlist = ModList::get_by_name(modlist_name)
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such modlist"))?;
+
+  let path = modlist.mergeinventory_path();
+
+  if !path.exists() {
+    return Ok(MergeInventory::default());
+  }
+
+  let mut inventory = parse_merge_inventory(&read_script(&path)?)
+    .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+  if inventory.merges.is_empty() {
+    return Ok(inventory);
+  }
+
+  // the disabled mods of the modlist don't count, the game doesn't load them
+  let mods = open_effective_entries(modlist_name)?
+    .entries
+    .into_iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~'))
+    .map(|entry| entry.name.to_lowercase())
+    .collect::<HashSet<_>>();
+
+  for merge in &mut inventory.merges {
+    merge.missing_mods = merge
+      .included_mods
+      .iter()
+      .filter(|mod_name| !mods.contains(&mod_name.to_lowercase()))
+      .cloned()
+      .collect();
+  }
+
+  Ok(inventory)
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use toml;
+
+use crate::constants;
+use crate::models::modlist::ModList;
+use crate::utils::hash::hash_file;
+
+/// whether a merged script still matches the files it was merged from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MergeStatus {
+  UpToDate,
+
+  /// one of the files changed since the merge
+  Stale,
+
+  /// one of the files is gone since the merge
+  Orphaned,
+}
+
+impl MergeStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      MergeStatus::UpToDate => "up to date",
+      MergeStatus::Stale => "stale",
+      MergeStatus::Orphaned => "orphaned",
+    }
+  }
+}
+
+/// a file a merged script was merged from, and its hash at the time
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MergeSource {
+  pub mod_name: String,
+  pub modlist: String,
+  pub path: PathBuf,
+  pub hash: String,
+
+  /// the size and modification time of the file at the time, the file is only
+  /// hashed again when one of them changed.
+  #[serde(default)]
+  pub size: u64,
+  #[serde(default)]
+  pub modified: u64,
+}
+
+/// the modification time of the file in nanoseconds, 0 when it is unknown
+fn modified_stamp(metadata: &fs::Metadata) -> u64 {
+  metadata
+    .modified()
+    .ok()
+    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+    .map(|duration| duration.as_nanos() as u64)
+    .unwrap_or(0)
+}
+
+impl MergeSource {
+  pub fn from_path(mod_name: &str, modlist: &str, path: &PathBuf) -> std::io::Result<MergeSource> {
+    let metadata = fs::metadata(path)?;
+
+    Ok(MergeSource {
+      mod_name: mod_name.to_owned(),
+      modlist: modlist.to_owned(),
+      path: path.clone(),
+      hash: hash_file(path)?,
+      size: metadata.len(),
+      modified: modified_stamp(&metadata),
+    })
+  }
+
+  pub fn status(&self) -> MergeStatus {
+    let metadata = match fs::metadata(&self.path) {
+      Ok(metadata) => metadata,
+      Err(_) => return MergeStatus::Orphaned,
+    };
+
+    if self.modified != 0
+      && metadata.len() == self.size
+      && modified_stamp(&metadata) == self.modified
+    {
+      return MergeStatus::UpToDate;
+    }
+
+    match hash_file(&self.path) {
+      Ok(hash) if hash == self.hash => MergeStatus::UpToDate,
+      Ok(_) => MergeStatus::Stale,
+      Err(_) => MergeStatus::Orphaned,
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MergedScript {
+  /// the path of the script relative to the `scripts` folder
+  pub script: String,
+
+  /// the vanilla script used as the common ancestor, if there is one
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub original: Option<MergeSource>,
+
+  /// the scripts of the mods, in the order they were merged
+  #[serde(default)]
+  pub sources: Vec<MergeSource>,
+}
+
+impl MergedScript {
+  /// the worst status of the files the script was merged from
+  pub fn status(&self) -> MergeStatus {
+    self
+      .original
+      .iter()
+      .chain(self.sources.iter())
+      .map(|source| source.status())
+      .max()
+      .unwrap_or(MergeStatus::UpToDate)
+  }
+}
+
+/// the files every merged script of the last merge was made from. It is written
+/// in the `merges` folder of the modlist at the end of every merge.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct MergeManifest {
+  #[serde(default)]
+  pub merged_at: String,
+
+  #[serde(default)]
+  pub scripts: Vec<MergedScript>,
+}
+
+impl MergeManifest {
+  pub fn path(modlist: &ModList) -> PathBuf {
+    modlist
+      .merges_path()
+      .join(constants::MODLIST_MERGE_MANIFEST_NAME)
+  }
+
+  /// reads the manifest of the last merge, `None` if the modlist was never
+  /// merged.
+  pub fn read_from_disk(modlist: &ModList) -> std::io::Result<Option<MergeManifest>> {
+    let path = MergeManifest::path(modlist);
+
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    let text = fs::read_to_string(path)?;
+    let manifest: MergeManifest = toml::from_str(&text)?;
+
+    Ok(Some(manifest))
+  }
+
+  pub fn write_to_disk(&self, modlist: &ModList) -> Result<(), String> {
+    let content =
+      toml::to_string_pretty(&self).map_err(|_| "manifest serialization error".to_owned())?;
+
+    fs::write(MergeManifest::path(modlist), content)
+      .map_err(|err| format!("disk write error {}", err))?;
+
+    Ok(())
+  }
+
+  /// the status of every merged script, and the worst of them for the whole
+  /// merge.
+  pub fn status(&self) -> (MergeStatus, Vec<(&MergedScript, MergeStatus)>) {
+    let scripts = self
+      .scripts
+      .iter()
+      .map(|script| (script, script.status()))
+      .collect::<Vec<_>>();
+
+    let status = scripts
+      .iter()
+      .map(|(_, status)| *status)
+      .max()
+      .unwrap_or(MergeStatus::UpToDate);
+
+    (status, scripts)
+  }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use toml;
+
+use crate::constants;
+use crate::models::modlist::ModList;
+use crate::utils::hash::hash_bytes;
+
+/// the resolution the user gave to a conflict, replayed when the same conflict
+/// shows up in a later merge.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MergeResolution {
+  /// the script the conflict is in, relative to the `scripts` folder
+  pub script: String,
+
+  /// the mod whose changes conflicted with the previous mods
+  pub mod_name: String,
+
+  /// the hash of the conflict as it was in the file, markers and contexts
+  /// included.
+  pub hunk: String,
+
+  /// the conflict as it was in the file, kept to review the resolution
+  pub conflict: String,
+
+  /// the text that replaced the conflict
+  pub resolution: String,
+}
+
+impl MergeResolution {
+  pub fn matches(&self, script: &str, mod_name: &str, hunk: &str) -> bool {
+    self.hunk == hunk
+      && self.mod_name == mod_name
+      && self.script.to_lowercase() == script.to_lowercase()
+  }
+}
+
+/// the resolutions remembered for the merges of a modlist. They are stored in
+/// the modlist directory, next to its config.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct MergeResolutions {
+  #[serde(default)]
+  pub resolutions: Vec<MergeResolution>,
+}
+
+/// identifies a conflict by its content only, so the same conflict is found
+/// again wherever it is in the file.
+pub fn hunk_hash(region: &str) -> String {
+  hash_bytes(region.as_bytes())
+}
+
+impl MergeResolutions {
+  pub fn path(modlist: &ModList) -> PathBuf {
+    modlist
+      .path()
+      .join(constants::MODLIST_MERGE_RESOLUTIONS_NAME)
+  }
+
+  /// reads the resolutions from the disk, or returns no resolution if the
+  /// modlist was never merged.
+  pub fn read_from_disk(modlist: &ModList) -> std::io::Result<MergeResolutions> {
+    let path = MergeResolutions::path(modlist);
+
+    if !path.exists() {
+      return Ok(MergeResolutions::default());
+    }
+
+    let text = fs::read_to_string(path)?;
+    let resolutions: MergeResolutions = toml::from_str(&text)?;
+
+    Ok(resolutions)
+  }
+
+  pub fn write_to_disk(&self, modlist: &ModList) -> Result<(), String> {
+    let content =
+      toml::to_string_pretty(&self).map_err(|_| "resolutions serialization error".to_owned())?;
+
+    fs::write(MergeResolutions::path(modlist), content)
+      .map_err(|err| format!("disk write error {}", err))?;
+
+    Ok(())
+  }
+
+  pub fn find(&self, script: &str, mod_name: &str, hunk: &str) -> Option<&MergeResolution> {
+    self
+      .resolutions
+      .iter()
+      .find(|resolution| resolution.matches(script, mod_name, hunk))
+  }
+
+  /// stores the resolution, it replaces the previous resolution of the same
+  /// conflict.
+  pub fn remember(&mut self, resolution: MergeResolution) {
+    self.forget(&resolution.script, &resolution.mod_name, &resolution.hunk);
+    self.resolutions.push(resolution);
+  }
+
+  /// removes the resolution of the conflict, returns whether there was one
+  pub fn forget(&mut self, script: &str, mod_name: &str, hunk: &str) -> bool {
+    let count = self.resolutions.len();
+
+    self
+      .resolutions
+      .retain(|resolution| !resolution.matches(script, mod_name, hunk));
+
+    self.resolutions.len() != count
+  }
+}
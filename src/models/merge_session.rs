@@ -0,0 +1,283 @@
+use actix::{Message, Recipient};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::models::modlist::ModList;
+use crate::models::script_merge::{ScriptMerger, SocketMessage};
+use crate::utils::hash::hash_bytes;
+
+/// a serialized `SocketMessage` sent to the websockets observing a session
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+pub struct SessionEvent(pub String);
+
+/// what the session is doing, and what is sent again to the websockets that
+/// connect in the middle of the merge.
+struct SessionState {
+  /// the conflicts the merge waits resolutions for
+  pending: Option<SocketMessage>,
+
+  /// the last progress of the merge, while it merges the scripts
+  progress: Option<SocketMessage>,
+
+  /// the last message of the merge, once every script is merged, the merge
+  /// failed or was cancelled.
+  last_message: Option<SocketMessage>,
+
+  listeners: BTreeMap<usize, Recipient<SessionEvent>>,
+  next_listener_id: usize,
+}
+
+/// a merge of the scripts of a modlist that outlives the websockets observing
+/// it, so the page can be refreshed or opened in several tabs.
+pub struct MergeSession {
+  pub id: String,
+  pub modlist_name: String,
+
+  merger: Mutex<ScriptMerger>,
+  state: Mutex<SessionState>,
+  cancelled: AtomicBool,
+}
+
+/// the sessions by id. A session is removed once it is over and its last
+/// listener is gone, or when the modlist is merged again.
+static MERGE_SESSIONS: Mutex<BTreeMap<String, Arc<MergeSession>>> = Mutex::new(BTreeMap::new());
+
+fn lock_error() -> std::io::Error {
+  std::io::Error::other("the merge sessions are poisoned")
+}
+
+fn serialize(message: &SocketMessage) -> String {
+  serde_json::to_string(message).unwrap_or_default()
+}
+
+impl MergeSession {
+  /// starts merging the scripts of the modlist, or returns the session that is
+  /// already merging them.
+  pub fn start(modlist: &ModList) -> std::io::Result<Arc<MergeSession>> {
+    let mut sessions = MERGE_SESSIONS.lock().map_err(|_| lock_error())?;
+
+    let running = sessions
+      .values()
+      .find(|session| session.modlist_name == modlist.name && !session.is_finished());
+
+    if let Some(session) = running {
+      return Ok(session.clone());
+    }
+
+    // the sessions of the modlist that are over are replaced by the new one
+    sessions.retain(|_, session| session.modlist_name != modlist.name);
+
+    let id =
+      hash_bytes(format!("{}:{}", modlist.name, chrono::Utc::now()).as_bytes())[..12].to_owned();
+
+    let session = Arc::new(MergeSession {
+      id: id.clone(),
+      modlist_name: modlist.name.clone(),
+      merger: Mutex::new(ScriptMerger::new(modlist)?),
+      state: Mutex::new(SessionState {
+        pending: None,
+        progress: None,
+        last_message: None,
+        listeners: BTreeMap::new(),
+        next_listener_id: 0,
+      }),
+      cancelled: AtomicBool::new(false),
+    });
+
+    sessions.insert(id, session.clone());
+    MergeSession::advance(session.clone());
+
+    Ok(session)
+  }
+
+  pub fn get(id: &str) -> Option<Arc<MergeSession>> {
+    MERGE_SESSIONS
+      .lock()
+      .ok()
+      .and_then(|sessions| sessions.get(id).cloned())
+  }
+
+  /// whether the scripts of the modlist are being merged
+  pub fn is_running(modlist_name: &str) -> bool {
+    MERGE_SESSIONS
+      .lock()
+      .map(|sessions| {
+        sessions
+          .values()
+          .any(|session| session.modlist_name == modlist_name && !session.is_finished())
+      })
+      .unwrap_or(false)
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self
+      .state
+      .lock()
+      .map(|state| state.last_message.is_some())
+      .unwrap_or(true)
+  }
+
+  /// merges the scripts until the next conflicts in a separate thread, then
+  /// sends them to every listener.
+  fn advance(session: Arc<MergeSession>) {
+    thread::spawn(move || {
+      let result = match session.merger.lock() {
+        Ok(mut merger) => merger.next_conflict(&session.cancelled, |progress| {
+          session.publish(SocketMessage::progress(progress))
+        }),
+        Err(_) => Err(lock_error()),
+      };
+
+      // the cancellation sends its own message once the scripts are restored
+      if session.cancelled.load(Ordering::SeqCst) {
+        return;
+      }
+
+      let message = match result {
+        Ok(Some(conflicts)) => conflicts,
+        Ok(None) => SocketMessage::finished(),
+        Err(error) => SocketMessage::error(format!("could not merge the scripts. {}", error)),
+      };
+
+      session.publish(message);
+    });
+  }
+
+  fn publish(&self, message: SocketMessage) {
+    let mut state = match self.state.lock() {
+      Ok(state) => state,
+      Err(_) => return,
+    };
+
+    let event = SessionEvent(serialize(&message));
+
+    if !message.conflicts.is_empty() {
+      state.pending = Some(message);
+    } else if message.progress.is_some() {
+      state.progress = Some(message);
+    } else {
+      state.pending = None;
+      state.last_message = Some(message);
+    }
+
+    for listener in state.listeners.values() {
+      listener.do_send(event.clone());
+    }
+  }
+
+  /// adds a listener to the session, it immediately receives the pending
+  /// conflicts or the last message if there is one. Returns the id to remove
+  /// it later.
+  pub fn subscribe(&self, listener: Recipient<SessionEvent>) -> Option<usize> {
+    let mut state = self.state.lock().ok()?;
+
+    let current = state
+      .last_message
+      .as_ref()
+      .or(state.pending.as_ref())
+      .or(state.progress.as_ref());
+    if let Some(message) = current {
+      listener.do_send(SessionEvent(serialize(message)));
+    }
+
+    let id = state.next_listener_id;
+    state.next_listener_id += 1;
+    state.listeners.insert(id, listener);
+
+    Some(id)
+  }
+
+  /// removes the listener from the session. The session is forgotten when it
+  /// is over and it was the last listener, it received the last message when
+  /// it subscribed or when the message was published.
+  pub fn unsubscribe(&self, listener_id: usize) {
+    let is_over = match self.state.lock() {
+      Ok(mut state) => {
+        state.listeners.remove(&listener_id);
+
+        state.listeners.is_empty() && state.last_message.is_some()
+      }
+      Err(_) => return,
+    };
+
+    if is_over {
+      if let Ok(mut sessions) = MERGE_SESSIONS.lock() {
+        sessions.remove(&self.id);
+      }
+    }
+  }
+
+  /// applies the resolutions of the pending conflicts and continues the merge.
+  /// Only the first resolutions are accepted when several tabs send some for
+  /// the same conflicts.
+  pub fn resolve(session: &Arc<MergeSession>, resolutions: &SocketMessage) -> Result<(), String> {
+    if session.cancelled.load(Ordering::SeqCst) {
+      return Err(String::from("the merge was cancelled"));
+    }
+
+    let pending = session
+      .state
+      .lock()
+      .map_err(|_| String::from("the merge session is poisoned"))?
+      .pending
+      .take()
+      .ok_or_else(|| String::from("the merge is not waiting for resolutions"))?;
+
+    let result = match session.merger.lock() {
+      Ok(mut merger) => merger.resolve(resolutions),
+      Err(_) => Err(String::from("the merge session is poisoned")),
+    };
+
+    match result {
+      Ok(()) => {
+        MergeSession::advance(session.clone());
+
+        Ok(())
+      }
+      Err(error) => {
+        // the conflicts are still there, they can be resolved again
+        if let Ok(mut state) = session.state.lock() {
+          state.pending = Some(pending);
+        }
+
+        Err(error)
+      }
+    }
+  }
+
+  /// stops the merge and puts back the merged scripts that were there before
+  /// it started. The scripts are restored in a separate thread since the merge
+  /// may be in the middle of a script.
+  pub fn cancel(session: &Arc<MergeSession>) -> Result<(), String> {
+    if session.is_finished() {
+      return Err(String::from("the merge is already over"));
+    }
+
+    if session.cancelled.swap(true, Ordering::SeqCst) {
+      return Err(String::from("the merge is already being cancelled"));
+    }
+
+    let session = session.clone();
+    thread::spawn(move || {
+      let result = match session.merger.lock() {
+        Ok(mut merger) => merger.cancel(),
+        Err(_) => Err(lock_error()),
+      };
+
+      let message = match result {
+        Ok(()) => SocketMessage::cancelled(),
+        Err(error) => SocketMessage::error(format!(
+          "could not restore the previous merged scripts. {}",
+          error
+        )),
+      };
+
+      session.publish(message);
+    });
+
+    Ok(())
+  }
+}
@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::models::bundle::BundleEntry;
+
+/// the magic every metadata store starts with
+const METADATA_STORE_MAGIC: &[u8; 4] = b"\x03VCS";
+
+const METADATA_STORE_VERSION: u32 = 6;
+
+/// the 64 bits FNV-1a hash of the path, the game looks the files up with it
+fn path_hash(path: &str) -> u64 {
+  path
+    .to_lowercase()
+    .bytes()
+    .fold(0xcbf29ce484222325, |hash, byte| {
+      (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// writes the number with the variable length encoding of the game. The first
+/// byte holds 6 bits, the sign and whether more bytes follow, the next ones 7
+/// bits and whether more bytes follow.
+fn write_vlq(output: &mut Vec<u8>, value: u32) {
+  let mut value = value;
+  let mut byte = (value & 0x3F) as u8;
+  value >>= 6;
+
+  if value != 0 {
+    byte |= 0x40;
+  }
+
+  output.push(byte);
+
+  while value != 0 {
+    let mut byte = (value & 0x7F) as u8;
+    value >>= 7;
+
+    if value != 0 {
+      byte |= 0x80;
+    }
+
+    output.push(byte);
+  }
+}
+
+fn write_u32s(output: &mut Vec<u8>, values: &[u32]) {
+  for value in values {
+    output.extend_from_slice(&value.to_le_bytes());
+  }
+}
+
+/// the strings of the store, they are null-terminated and referenced by their
+/// offset.
+struct StringTable {
+  bytes: Vec<u8>,
+  offsets: BTreeMap<String, u32>,
+}
+
+impl StringTable {
+  fn new() -> StringTable {
+    StringTable {
+      // the empty string is at the offset 0, for the unused first rows
+      bytes: vec![0],
+      offsets: BTreeMap::new(),
+    }
+  }
+
+  fn add(&mut self, string: &str) -> u32 {
+    if let Some(offset) = self.offsets.get(string) {
+      return *offset;
+    }
+
+    let offset = self.bytes.len() as u32;
+    self.bytes.extend_from_slice(string.as_bytes());
+    self.bytes.push(0);
+    self.offsets.insert(string.to_owned(), offset);
+
+    offset
+  }
+}
+
+/// writes the metadata store of a mod that has a single bundle. The game reads
+/// the store instead of the bundles to know which files they contain, so a
+/// bundle without its store is ignored.
+///
+/// The store is made of the string table, then the files, the file entries,
+/// the bundles, the buffers, the directories and the hashes of the paths. The
+/// first row of the files, entries and bundles is unused and left empty.
+pub fn write_metadata_store(
+  path: &Path, bundle_name: &str, entries: &[BundleEntry],
+) -> std::io::Result<()> {
+  fs::write(path, encode_metadata_store(bundle_name, entries))
+}
+
+fn encode_metadata_store(bundle_name: &str, entries: &[BundleEntry]) -> Vec<u8> {
+  let mut strings = StringTable::new();
+
+  // the directories and their parent, the root directory is the first one
+  let mut directories: Vec<(u32, u32)> = vec![(0, 0)];
+  let mut directory_ids: BTreeMap<String, u32> = BTreeMap::new();
+
+  for entry in entries {
+    let key = entry.key();
+    let components = key.split('\\').collect::<Vec<_>>();
+    let mut parent = 0;
+    let mut directory = String::new();
+
+    // the last component is the name of the file
+    for component in &components[..components.len() - 1] {
+      if !directory.is_empty() {
+        directory.push('\\');
+      }
+      directory.push_str(component);
+
+      parent = match directory_ids.get(&directory) {
+        Some(id) => *id,
+        None => {
+          let id = directories.len() as u32;
+          directories.push((strings.add(component), parent));
+          directory_ids.insert(directory.clone(), id);
+
+          id
+        }
+      };
+    }
+  }
+
+  let bundle_name_offset = strings.add(bundle_name);
+
+  let mut files = vec![[0u32; 9]];
+  let mut file_entries = vec![[0u32; 5]];
+  let mut hashes = Vec::with_capacity(entries.len());
+
+  for (i, entry) in entries.iter().enumerate() {
+    let file_id = (i + 1) as u32;
+    let hash = path_hash(&entry.key());
+
+    // the name offset, the hash on two u32, the sizes, the first entry, the
+    // compression and the range of the buffers of the file.
+    files.push([
+      strings.add(&entry.path),
+      hash as u32,
+      (hash >> 32) as u32,
+      entry.compressed_size,
+      entry.size,
+      file_id,
+      entry.compression.to_u32(),
+      0,
+      0,
+    ]);
+
+    // the file, the bundle, the offset and size in the bundle and the next
+    // entry of the same file.
+    file_entries.push([file_id, 1, entry.offset, entry.compressed_size, 0]);
+
+    hashes.push((hash, file_id));
+  }
+
+  hashes.sort();
+
+  let data_size = entries
+    .iter()
+    .map(|entry| entry.compressed_size)
+    .sum::<u32>();
+  let data_offset = entries.iter().map(|entry| entry.offset).min().unwrap_or(0);
+
+  // the name, the first entry, the number of entries, the size and offset of
+  // the data, and the size of the data read at once.
+  let bundles = vec![
+    [0u32; 6],
+    [
+      bundle_name_offset,
+      1,
+      entries.len() as u32,
+      data_size,
+      data_offset,
+      0,
+    ],
+  ];
+
+  let max_size = entries.iter().map(|entry| entry.size).max().unwrap_or(0);
+  let max_compressed_size = entries
+    .iter()
+    .map(|entry| entry.compressed_size)
+    .max()
+    .unwrap_or(0);
+
+  let mut output = Vec::new();
+  output.extend_from_slice(METADATA_STORE_MAGIC);
+  write_u32s(
+    &mut output,
+    &[METADATA_STORE_VERSION, max_compressed_size, max_size],
+  );
+
+  write_vlq(&mut output, strings.bytes.len() as u32);
+  output.extend_from_slice(&strings.bytes);
+
+  write_vlq(&mut output, files.len() as u32);
+  for file in &files {
+    write_u32s(&mut output, file);
+  }
+
+  write_vlq(&mut output, file_entries.len() as u32);
+  for file_entry in &file_entries {
+    write_u32s(&mut output, file_entry);
+  }
+
+  write_vlq(&mut output, bundles.len() as u32);
+  for bundle in &bundles {
+    write_u32s(&mut output, bundle);
+  }
+
+  // the merged files are xml files, none of them has buffers
+  write_vlq(&mut output, 0);
+
+  write_vlq(&mut output, directories.len() as u32);
+  for (name, parent) in &directories {
+    write_u32s(&mut output, &[*name, *parent]);
+  }
+
+  write_vlq(&mut output, hashes.len() as u32);
+  for (hash, file_id) in &hashes {
+    output.extend_from_slice(&hash.to_le_bytes());
+    output.extend_from_slice(&file_id.to_le_bytes());
+  }
+
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::models::bundle::BundleCompression;
+
+  /// reads the store back the way the game does, every read past the end of
+  /// the buffer is an error.
+  struct StoreReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> StoreReader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], String> {
+      let end = self.offset + size;
+      let bytes = self
+        .bytes
+        .get(self.offset..end)
+        .ok_or_else(|| format!("{} bytes past the end at {}", size, self.offset))?;
+
+      self.offset = end;
+
+      Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+      let mut buffer = [0; 4];
+      buffer.copy_from_slice(self.take(4)?);
+
+      Ok(u32::from_le_bytes(buffer))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+      let mut buffer = [0; 8];
+      buffer.copy_from_slice(self.take(8)?);
+
+      Ok(u64::from_le_bytes(buffer))
+    }
+
+    fn vlq(&mut self) -> Result<u32, String> {
+      let byte = self.take(1)?[0];
+      let mut value = (byte & 0x3F) as u32;
+      let mut shift = 6;
+      let mut more = byte & 0x40 != 0;
+
+      while more {
+        let byte = self.take(1)?[0];
+        value |= ((byte & 0x7F) as u32) << shift;
+        shift += 7;
+        more = byte & 0x80 != 0;
+      }
+
+      Ok(value)
+    }
+
+    fn rows<const N: usize>(&mut self) -> Result<Vec<[u32; N]>, String> {
+      let count = self.vlq()?;
+      let mut rows = Vec::new();
+
+      for _ in 0..count {
+        let mut row = [0; N];
+
+        for value in row.iter_mut() {
+          *value = self.u32()?;
+        }
+
+        rows.push(row);
+      }
+
+      Ok(rows)
+    }
+  }
+
+  struct ParsedStore {
+    strings: Vec<u8>,
+    files: Vec<[u32; 9]>,
+    file_entries: Vec<[u32; 5]>,
+    bundles: Vec<[u32; 6]>,
+    directories: Vec<[u32; 2]>,
+    hashes: Vec<(u64, u32)>,
+  }
+
+  impl ParsedStore {
+    fn string(&self, offset: u32) -> String {
+      let bytes = &self.strings[offset as usize..];
+      let end = bytes.iter().position(|byte| *byte == 0).unwrap();
+
+      String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+  }
+
+  fn parse(bytes: &[u8]) -> Result<ParsedStore, String> {
+    let mut reader = StoreReader { bytes, offset: 0 };
+
+    if reader.take(4)? != METADATA_STORE_MAGIC {
+      return Err(String::from("not a metadata store"));
+    }
+
+    if reader.u32()? != METADATA_STORE_VERSION {
+      return Err(String::from("unknown version"));
+    }
+
+    // the largest compressed and decompressed sizes
+    reader.u32()?;
+    reader.u32()?;
+
+    let strings_size = reader.vlq()? as usize;
+    let strings = reader.take(strings_size)?.to_vec();
+    let files = reader.rows::<9>()?;
+    let file_entries = reader.rows::<5>()?;
+    let bundles = reader.rows::<6>()?;
+
+    if reader.vlq()? != 0 {
+      return Err(String::from("unexpected buffers"));
+    }
+
+    let directories = reader.rows::<2>()?;
+    let mut hashes = Vec::new();
+
+    for _ in 0..reader.vlq()? {
+      hashes.push((reader.u64()?, reader.u32()?));
+    }
+
+    if reader.offset != bytes.len() {
+      return Err(format!("{} bytes left", bytes.len() - reader.offset));
+    }
+
+    Ok(ParsedStore {
+      strings,
+      files,
+      file_entries,
+      bundles,
+      directories,
+      hashes,
+    })
+  }
+
+  fn entry(path: &str, size: u32, offset: u32) -> BundleEntry {
+    BundleEntry {
+      path: path.to_owned(),
+      size,
+      compressed_size: size / 2,
+      offset,
+      compression: BundleCompression::Zlib,
+    }
+  }
+
+  fn sample_entries() -> Vec<BundleEntry> {
+    vec![
+      entry("gameplay\\items\\def_item_weapons.xml", 4000, 0x160),
+      entry(
+        "gameplay\\abilities\\monster_base_abl.xml",
+        3000,
+        0x160 + 2000,
+      ),
+      entry("root.xml", 100, 0x160 + 3500),
+    ]
+  }
+
+  #[test]
+  fn written_store_reads_back() {
+    let entries = sample_entries();
+    let store = parse(&encode_metadata_store("blob0.bundle", &entries)).unwrap();
+
+    assert_eq!(store.bundles.len(), 2);
+    let bundle = store.bundles[1];
+    assert_eq!(store.string(bundle[0]), "blob0.bundle");
+    assert_eq!(bundle[2], entries.len() as u32);
+    assert_eq!(bundle[3], 2000 + 1500 + 50);
+    assert_eq!(bundle[4], 0x160);
+
+    assert_eq!(store.files.len(), entries.len() + 1);
+    assert_eq!(store.file_entries.len(), entries.len() + 1);
+
+    for (i, entry) in entries.iter().enumerate() {
+      let file = store.files[i + 1];
+      let file_entry = store.file_entries[i + 1];
+
+      assert_eq!(store.string(file[0]), entry.path);
+      assert_eq!(file[3], entry.compressed_size);
+      assert_eq!(file[4], entry.size);
+      assert_eq!(file[6], BundleCompression::Zlib.to_u32());
+      assert_eq!(file_entry[2], entry.offset);
+      assert_eq!(file_entry[3], entry.compressed_size);
+    }
+
+    // the hashes are sorted for the lookups of the game
+    assert!(store.hashes.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    for (hash, file_id) in &store.hashes {
+      let path = store.string(store.files[*file_id as usize][0]);
+
+      assert_eq!(*hash, path_hash(&path));
+    }
+  }
+
+  #[test]
+  fn directories_are_shared_and_point_to_their_parent() {
+    let store = parse(&encode_metadata_store("blob0.bundle", &sample_entries())).unwrap();
+    let names = store
+      .directories
+      .iter()
+      .map(|directory| store.string(directory[0]))
+      .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["", "gameplay", "items", "abilities"]);
+    assert_eq!(store.directories[1][1], 0);
+    assert_eq!(store.directories[2][1], 1);
+    assert_eq!(store.directories[3][1], 1);
+  }
+
+  #[test]
+  fn vlq_reads_back() {
+    for value in [0, 1, 63, 64, 8191, 8192, 1 << 20, u32::MAX] {
+      let mut output = Vec::new();
+      write_vlq(&mut output, value);
+
+      let mut reader = StoreReader {
+        bytes: &output,
+        offset: 0,
+      };
+
+      assert_eq!(reader.vlq(), Ok(value));
+      assert_eq!(reader.offset, output.len());
+    }
+  }
+
+  #[test]
+  fn short_store_is_an_error() {
+    let bytes = encode_metadata_store("blob0.bundle", &sample_entries());
+
+    for size in 0..bytes.len() {
+      assert!(parse(&bytes[..size]).is_err(), "{} bytes", size);
+    }
+  }
+
+  #[test]
+  fn malformed_store_is_an_error() {
+    let mut bytes = encode_metadata_store("blob0.bundle", &sample_entries());
+    bytes[0] = 0;
+
+    assert!(parse(&bytes).is_err());
+    assert!(parse(b"\x03VCS\x06\x00\x00\x00").is_err());
+  }
+}
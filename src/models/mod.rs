@@ -1 +1,25 @@
+pub mod bundle;
+pub mod bundle_conflicts;
+pub mod bundle_merge;
+pub mod database_config;
+pub mod database_index;
+pub mod import_graph;
+pub mod input_settings;
+pub mod menu_check;
+pub mod menu_filelist;
+pub mod merge_inventory;
+pub mod merge_manifest;
+pub mod merge_resolutions;
+pub mod merge_session;
+pub mod metadata_store;
 pub mod modlist;
+pub mod mods_settings;
+pub mod script_conflicts;
+pub mod script_merge;
+pub mod search_index;
+pub mod settings_file;
+pub mod string_conflicts;
+pub mod texture_cache;
+pub mod texture_conflicts;
+pub mod user_settings;
+pub mod w3strings;
@@ -6,11 +6,20 @@ use std::path::{Path, PathBuf};
 use toml;
 
 use crate::constants;
-use crate::utils::symlinks::{make_symlink, remove_symlink, remove_symlinks, symlink_children};
+use crate::models::input_settings;
+use crate::models::menu_filelist;
+use crate::models::mods_settings;
+use crate::utils::symlinks::{
+  has_symlinks, make_symlink, remove_symlink, remove_symlinks, symlink_children,
+};
 
 #[derive(Deserialize, Serialize)]
 pub struct ModListConfig {
   imports: Vec<String>,
+  group: Option<String>,
+
+  /// the numeric visibility level the modlists had before named groups. It is
+  /// only read to migrate it to a group and is never written back.
   visibility: Option<i64>,
 }
 
@@ -20,6 +29,27 @@ pub struct ImportedModlist {
   pub order: i64,
 }
 
+/// a modlist that imports another modlist, either directly or through one of
+/// its own imports.
+#[derive(Clone, Debug)]
+pub struct ModListDependent {
+  pub modlist: ModList,
+
+  /// the name of the modlist the dependent imports directly. It is the
+  /// depended-on modlist itself when the import is direct, or one of the other
+  /// dependents when the import is transitive.
+  pub imported_through: String,
+
+  /// the position of `imported_through` in the dependent's imports, starting
+  /// at 0 for the first modlist to be loaded.
+  pub import_position: usize,
+
+  /// 1 for direct imports, 2 for the modlists importing a direct dependent, etc...
+  pub depth: usize,
+
+  pub imports_loaded: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct ModList {
   pub name: String,
@@ -29,7 +59,8 @@ pub struct ModList {
   /// to bottom.
   pub imported_modlists: Vec<String>,
 
-  pub visibility: i64,
+  /// the name of the group the modlist is displayed in, from the database config
+  pub group: Option<String>,
 }
 
 impl ModList {
@@ -37,7 +68,7 @@ impl ModList {
     ModList {
       name,
       imported_modlists: Vec::new(),
-      visibility: 0,
+      group: None,
     }
   }
 
@@ -104,11 +135,39 @@ impl ModList {
       self.import_modlist(&import);
     }
 
-    self.visibility = toml_config.visibility.unwrap_or(0);
+    // the modlists that were not migrated to groups yet still have a level
+    let legacy_visibility = toml_config.visibility;
+    self.group = toml_config
+      .group
+      .or_else(|| legacy_visibility.and_then(ModList::visibility_group_name));
 
     Ok(())
   }
 
+  /// returns the numeric visibility level stored in the config file, if the
+  /// modlist was not migrated to groups yet.
+  pub fn read_legacy_visibility(&self) -> std::io::Result<Option<i64>> {
+    let config_path = self.config_path();
+
+    if !config_path.exists() {
+      return Ok(None);
+    }
+
+    let text = fs::read_to_string(config_path)?;
+    let toml_config: ModListConfig = toml::from_str(&text)?;
+
+    Ok(toml_config.visibility)
+  }
+
+  /// the name of the group a legacy visibility level is migrated to. The level
+  /// 0 was the default level so it doesn't get a group.
+  pub fn visibility_group_name(level: i64) -> Option<String> {
+    match level {
+      0 => None,
+      level => Some(format!("level {}", level)),
+    }
+  }
+
   pub fn read_metadata_from_disk_copy(&self) -> std::io::Result<Self> {
     let mut copy = self.clone();
 
@@ -124,7 +183,8 @@ impl ModList {
         .into_iter()
         .map(String::from)
         .collect(),
-      visibility: Some(self.visibility),
+      group: self.group.clone(),
+      visibility: None,
     };
 
     let content =
@@ -181,9 +241,164 @@ impl ModList {
       symlink_children(modlist.saves_path(), self.saves_path())?;
     }
 
+    self.link_own_merge()?;
+    self.link_imported_merges()?;
+    self.update_generated_files();
+
+    Ok(())
+  }
+
+  /// updates the files of the modlist that are generated from its current
+  /// mods and menus. None of them is needed to play, the errors are logged so
+  /// they don't stop the loading or the install.
+  fn update_generated_files(&self) {
+    if let Err(error) = self.update_menu_filelists() {
+      println!("could not update the menu file lists: {}", error);
+    }
+
+    if let Err(error) = self.merge_input_settings() {
+      println!("could not merge the input settings: {}", error);
+    }
+
+    if let Err(error) = self.write_mods_settings() {
+      println!("could not write the mods settings: {}", error);
+    }
+  }
+
+  /// inserts the input snippets of the current mods in the input.settings file
+  /// of the modlist, see [`input_settings::merge_input_settings`].
+  pub fn merge_input_settings(&self) -> std::io::Result<()> {
+    input_settings::merge_input_settings(self)?;
+
     Ok(())
   }
 
+  /// writes the priorities of the current mods in the mods.settings file of the
+  /// modlist, see [`mods_settings::write_mods_settings`].
+  pub fn write_mods_settings(&self) -> std::io::Result<()> {
+    mods_settings::write_mods_settings(self)
+  }
+
+  /// rewrites the dx11 and dx12 file lists of the menus folder from the menus
+  /// it currently contains, see [`menu_filelist::update_filelists`]. The lists
+  /// of the vanilla modlist are the ones of the game and are left as they are.
+  pub fn update_menu_filelists(&self) -> std::io::Result<()> {
+    if self.name == "vanilla" {
+      return Ok(());
+    }
+
+    menu_filelist::update_filelists(&self.menus_path())
+  }
+
+  /// unload then load the imported modlists again, so the symlinks reflect the
+  /// current content of the imported modlists.
+  pub fn reload_imported_modlists(&mut self) -> std::io::Result<()> {
+    self.unload_imported_modlists()?;
+    self.load_imported_modlists()
+  }
+
+  /// returns whether the imports are currently loaded, which is when at least
+  /// one of the modlist directories contains a symlink. The links to the
+  /// merges don't count, the link to the modlist's own merge is there without
+  /// any import.
+  pub fn has_imports_loaded(&self) -> bool {
+    let has_mod_symlinks = fs::read_dir(self.mods_path())
+      .map(|children| {
+        children
+          .filter_map(|child| child.ok())
+          .filter(|child| !ModList::is_merge_link_name(&child.file_name().to_string_lossy()))
+          .any(|child| {
+            child
+              .path()
+              .symlink_metadata()
+              .map(|metadata| metadata.file_type().is_symlink())
+              .unwrap_or(false)
+          })
+      })
+      .unwrap_or(false);
+
+    has_mod_symlinks
+      || has_symlinks(&self.dlcs_path())
+      || has_symlinks(&self.menus_path())
+      || has_symlinks(&self.saves_path())
+      || has_symlinks(&self.content_path())
+      || has_symlinks(&self.bundles_path())
+  }
+
+  /// returns every modlist that imports the current modlist, directly or
+  /// transitively, in the order in which the imports should be reloaded: a
+  /// modlist comes after every dependent it imports.
+  pub fn get_dependents(&self) -> std::io::Result<Vec<ModListDependent>> {
+    let mut modlists = ModList::get_all();
+
+    for modlist in &mut modlists {
+      modlist.read_metadata_from_disk()?;
+    }
+
+    let mut dependents: Vec<ModListDependent> = Vec::new();
+    let mut visited = std::collections::HashSet::new();
+    visited.insert(self.name.clone());
+
+    // breadth first, so each dependent is listed with the shortest import
+    // chain leading to the current modlist. The visited set also protects
+    // against cyclic imports.
+    let mut current_level = vec![self.name.clone()];
+    let mut depth = 1;
+
+    while !current_level.is_empty() {
+      let mut next_level = Vec::new();
+
+      for modlist in &modlists {
+        if visited.contains(&modlist.name) {
+          continue;
+        }
+
+        let some_position = modlist
+          .imported_modlists
+          .iter()
+          .position(|import| current_level.contains(import));
+
+        if let Some(import_position) = some_position {
+          visited.insert(modlist.name.clone());
+          next_level.push(modlist.name.clone());
+
+          dependents.push(ModListDependent {
+            modlist: modlist.clone(),
+            imported_through: modlist.imported_modlists[import_position].clone(),
+            import_position,
+            depth,
+            imports_loaded: modlist.has_imports_loaded(),
+          });
+        }
+      }
+
+      current_level = next_level;
+      depth += 1;
+    }
+
+    // a dependent is reloaded after the dependents it imports, or it would
+    // load their content before it is up to date. The order above is kept
+    // otherwise, and a cycle is broken by taking its first dependent.
+    let mut ordered = Vec::with_capacity(dependents.len());
+
+    while !dependents.is_empty() {
+      let position = dependents
+        .iter()
+        .position(|dependent| {
+          !dependent
+            .modlist
+            .imported_modlists
+            .iter()
+            .any(|import| dependents.iter().any(|other| &other.modlist.name == import))
+        })
+        .unwrap_or(0);
+
+      ordered.push(dependents.remove(position));
+    }
+
+    Ok(ordered)
+  }
+
   pub fn path(&self) -> PathBuf {
     std::env::current_dir()
       .unwrap()
@@ -238,6 +453,101 @@ impl ModList {
       .join(constants::SCRIPTMERGER_MERGEDFILES_FOLDERNAME)
   }
 
+  pub fn merge_staging_path(&self) -> PathBuf {
+    self.path().join(constants::MODLIST_MERGE_STAGING_PATH)
+  }
+
+  pub fn mergedscripts_backup_path(&self) -> PathBuf {
+    self
+      .path()
+      .join(constants::MODLIST_MERGEDSCRIPTS_BACKUP_PATH)
+  }
+
+  /// where the scripts merged natively are recorded, see `specs/script-merging.md`
+  pub fn merges_path(&self) -> PathBuf {
+    self.path().join(constants::MODLIST_MERGES_PATH)
+  }
+
+  /// the result of the last merge of the modlist's scripts
+  pub fn own_merge_path(&self) -> PathBuf {
+    self.merges_path().join(constants::MODLIST_OWN_MERGE_NAME)
+  }
+
+  /// the name of the symlink to the merge of an imported modlist. The import
+  /// order is padded so the merges of the imports load after the modlist's own
+  /// merge, and in their import order.
+  pub fn import_merge_link_name(import_order: usize, modlist_name: &str) -> String {
+    format!("mod_{:04}_{}_mergedfiles", import_order, modlist_name)
+  }
+
+  /// whether the name is the one of a symlink to a merge, the modlist's own or
+  /// an imported one.
+  pub fn is_merge_link_name(name: &str) -> bool {
+    name.starts_with("mod_000") && name.ends_with("_mergedfiles")
+  }
+
+  /// creates the `mods/mod_000000_mergedfiles` symlink to the merge of the
+  /// modlist, if the modlist was merged. An existing symlink is replaced.
+  pub fn link_own_merge(&self) -> std::io::Result<()> {
+    let own_merge_path = self.own_merge_path();
+
+    if !own_merge_path.is_dir() {
+      return Ok(());
+    }
+
+    let link_path = self.mods_path().join(constants::MODLIST_OWN_MERGE_NAME);
+
+    if let Err(error) = remove_symlink(&link_path) {
+      println!("could not remove own merge symlink: {}", error);
+    }
+
+    make_symlink(&link_path, &own_merge_path)
+  }
+
+  /// creates a symlink to the merge of every imported modlist that was merged,
+  /// named after the import order. The symlinks to merges the imported modlists
+  /// brought with their mods are removed, only the direct imports are linked.
+  fn link_imported_merges(&self) -> std::io::Result<()> {
+    let own_merge_path = self.own_merge_path();
+
+    for child in fs::read_dir(self.mods_path())? {
+      let child = child?;
+      let name = child.file_name().to_string_lossy().to_string();
+
+      if !ModList::is_merge_link_name(&name) {
+        continue;
+      }
+
+      let is_own_link = fs::read_link(child.path())
+        .map(|target| target == own_merge_path)
+        .unwrap_or(true);
+
+      if !is_own_link {
+        remove_symlink(&child.path())?;
+      }
+    }
+
+    for (i, modlist_name) in self.imported_modlists.iter().enumerate() {
+      let modlist = match ModList::get_by_name(modlist_name) {
+        Some(modlist) => modlist,
+        None => continue,
+      };
+
+      let merge_path = modlist.own_merge_path();
+      if !merge_path.is_dir() {
+        continue;
+      }
+
+      let link_path = self
+        .mods_path()
+        .join(ModList::import_merge_link_name(i + 1, &modlist.name));
+
+      make_symlink(&link_path, &merge_path)?;
+    }
+
+    Ok(())
+  }
+
   pub fn backedup_mergedfiles_path(&self) -> PathBuf {
     self.mods_path().join(format!(
       "~{}",
@@ -282,6 +592,12 @@ impl ModList {
       .any(|modlist_name| modlist_name == modlist)
   }
 
+  /// a modlist can be installed only if it is the vanilla modlist or if it
+  /// imports it, as it would otherwise miss the base game files.
+  pub fn is_installable(&self) -> bool {
+    self.name == "vanilla" || self.has_modlist_imported("vanilla")
+  }
+
   pub fn is_valid(&self) -> bool {
     let dlcs_path = self.dlcs_path();
     let mods_path = self.mods_path();
@@ -360,6 +676,9 @@ impl ModList {
     make_symlink(&current_content_path, &self.content_path())?;
     make_symlink(&current_bundles_path, &self.bundles_path())?;
 
+    self.link_own_merge()?;
+    self.update_generated_files();
+
     // scriptermerger mergeinventory case:
     // special case to handle the scriptmerger mergeinventory.xml file.
     // because the tool uses a global database for the current state of the merge.
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use toml;
+
+use crate::constants;
+use crate::models::database_index::{open_effective_entries, EntryCategory};
+use crate::models::modlist::ModList;
+use crate::models::script_conflicts::is_mergedfiles;
+use crate::models::script_merge::read_script;
+use crate::models::settings_file::SettingsFile;
+use crate::utils::symlinks::write_replacing_symlink;
+
+/// the enabled state and priority the game gives to a mod of the modlist
+#[derive(Clone, Debug)]
+pub struct ModPriority {
+  pub mod_name: String,
+
+  /// the modlist the mod comes from, it is the modlist itself for its own mods
+  /// and for the links to the merges.
+  pub modlist: String,
+
+  pub enabled: bool,
+
+  /// the lower the priority the sooner the game loads the mod, and the more
+  /// its files win over the other mods.
+  pub priority: u32,
+
+  /// whether the values were edited, rather than generated from the load order
+  pub edited: bool,
+}
+
+/// the values edited by hand for a mod, they win over the load order
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModOverride {
+  pub enabled: bool,
+  pub priority: u32,
+}
+
+/// the edited values of the mods of the modlist, and the mods the manager
+/// wrote in the mods.settings file last time. The other entries of the file
+/// are left as they are.
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ModsSettingsConfig {
+  // the array comes first, toml can't write a value after a table
+  #[serde(default)]
+  pub managed: Vec<String>,
+
+  #[serde(default)]
+  pub overrides: BTreeMap<String, ModOverride>,
+}
+
+impl ModsSettingsConfig {
+  pub fn path(modlist: &ModList) -> PathBuf {
+    modlist.path().join(constants::MODLIST_MODS_SETTINGS_NAME)
+  }
+
+  pub fn read_from_disk(modlist: &ModList) -> std::io::Result<ModsSettingsConfig> {
+    let path = ModsSettingsConfig::path(modlist);
+
+    if !path.exists() {
+      return Ok(ModsSettingsConfig::default());
+    }
+
+    let text = fs::read_to_string(path)?;
+    let config: ModsSettingsConfig = toml::from_str(&text)?;
+
+    Ok(config)
+  }
+
+  pub fn write_to_disk(&self, modlist: &ModList) -> std::io::Result<()> {
+    let content = toml::to_string_pretty(&self).map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "mods settings serialization error",
+      )
+    })?;
+
+    fs::write(ModsSettingsConfig::path(modlist), content)
+  }
+}
+
+/// the mods of the modlist in the load order the manager uses: the merges
+/// first, then the other mods in alphabetical order. The disabled mods of the
+/// modlist, whose names start with a `~`, are included.
+fn get_load_order(modlist: &ModList) -> std::io::Result<Vec<(String, String)>> {
+  let mut mods = open_effective_entries(&modlist.name)?
+    .entries
+    .into_iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .map(|entry| (entry.name, entry.modlist))
+    .collect::<Vec<_>>();
+
+  // the links to the merges are symlinks, the index doesn't know them
+  for child in fs::read_dir(modlist.mods_path())? {
+    let name = child?.file_name().to_string_lossy().to_string();
+
+    let is_listed = mods.iter().any(|(mod_name, _)| mod_name == &name);
+
+    if ModList::is_merge_link_name(&name) && !is_listed {
+      mods.push((name, modlist.name.clone()));
+    }
+  }
+
+  mods.sort_by_key(|(name, _)| (!is_mergedfiles(name), name.to_lowercase()));
+
+  Ok(mods)
+}
+
+/// the enabled state and priority of every mod of the modlist, from the load
+/// order and the values edited by hand.
+pub fn get_mod_priorities(modlist: &ModList) -> std::io::Result<Vec<ModPriority>> {
+  let config = ModsSettingsConfig::read_from_disk(modlist)?;
+
+  let priorities = get_load_order(modlist)?
+    .into_iter()
+    .enumerate()
+    .map(|(position, (mod_name, source))| {
+      let edited = config.overrides.get(&mod_name);
+
+      ModPriority {
+        enabled: edited
+          .map(|edited| edited.enabled)
+          .unwrap_or(!mod_name.starts_with('~')),
+        priority: edited
+          .map(|edited| edited.priority)
+          .unwrap_or(position as u32),
+        edited: edited.is_some(),
+        mod_name,
+        modlist: source,
+      }
+    })
+    .collect();
+
+  Ok(priorities)
+}
+
+/// writes the `saves/mods.settings` file of the modlist from its mods. The
+/// entries of the mods the manager didn't write are kept, the entries of the
+/// mods it wrote before and that are gone are removed.
+pub fn write_mods_settings(modlist: &ModList) -> std::io::Result<()> {
+  let mut config = ModsSettingsConfig::read_from_disk(modlist)?;
+  let priorities = get_mod_priorities(modlist)?;
+
+  let settings_path = modlist.saves_path().join(constants::MODS_SETTINGS_NAME);
+
+  let current = if settings_path.exists() {
+    read_script(&settings_path)?
+  } else {
+    String::new()
+  };
+
+  let mut settings = SettingsFile::parse(&current);
+
+  for mod_name in &config.managed {
+    if !priorities
+      .iter()
+      .any(|mod_priority| &mod_priority.mod_name == mod_name)
+    {
+      settings.remove_context(mod_name);
+    }
+  }
+
+  for mod_priority in &priorities {
+    let enabled = if mod_priority.enabled { "1" } else { "0" };
+
+    settings.set(&mod_priority.mod_name, "Enabled", enabled);
+    settings.set(
+      &mod_priority.mod_name,
+      "Priority",
+      &mod_priority.priority.to_string(),
+    );
+  }
+
+  let content = settings.to_text();
+
+  if content != current {
+    write_replacing_symlink(&settings_path, content)?;
+  }
+
+  let managed = priorities
+    .into_iter()
+    .map(|mod_priority| mod_priority.mod_name)
+    .collect::<Vec<_>>();
+
+  if managed != config.managed {
+    config.managed = managed;
+    config.write_to_disk(modlist)?;
+  }
+
+  Ok(())
+}
+
+/// stores the values edited on the settings page, indexed by mod name, then
+/// writes the mods.settings file again. The values that match the load order
+/// are not stored so the mod follows the load order again.
+pub fn update_mod_priorities(
+  modlist: &ModList, values: &HashMap<String, ModOverride>,
+) -> std::io::Result<()> {
+  let mut config = ModsSettingsConfig::read_from_disk(modlist)?;
+
+  for (position, (mod_name, _)) in get_load_order(modlist)?.into_iter().enumerate() {
+    let value = match values.get(&mod_name) {
+      Some(value) => value,
+      None => continue,
+    };
+
+    let generated = ModOverride {
+      enabled: !mod_name.starts_with('~'),
+      priority: position as u32,
+    };
+
+    if value == &generated {
+      config.overrides.remove(&mod_name);
+    } else {
+      config.overrides.insert(mod_name, value.clone());
+    }
+  }
+
+  config.write_to_disk(modlist)?;
+  write_mods_settings(modlist)
+}
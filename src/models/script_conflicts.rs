@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::constants;
+use crate::models::database_index::{open_effective_entries, EffectiveEntries, EntryCategory};
+use crate::models::modlist::ModList;
+
+/// where the scripts are stored in a mod, relative to the mod folder
+const MOD_SCRIPTS_PREFIX: &str = "content/scripts/";
+
+/// a mod that contains a contested script
+#[derive(Clone, Debug)]
+pub struct ScriptConflictMod {
+  pub mod_name: String,
+
+  /// the modlist the mod comes from, it is the analyzed modlist itself when the
+  /// mod is not imported.
+  pub modlist: String,
+
+  /// the path of the script in the mod
+  pub path: PathBuf,
+}
+
+/// a script that is in more than one mod of the modlist
+#[derive(Clone, Debug)]
+pub struct ScriptConflict {
+  /// the path of the script relative to the `scripts` folder, for example
+  /// `game/player/r4Player.ws`
+  pub script: String,
+
+  /// the vanilla script from the `content` folder the script replaces, `None`
+  /// when it is a new script more than one mod adds.
+  pub vanilla_path: Option<PathBuf>,
+
+  /// the modlist whose `content` folder has the vanilla script
+  pub vanilla_modlist: Option<String>,
+
+  /// the mods that contain the script, in the order the game loads them. The
+  /// first one is the one the game uses when the script is not merged.
+  pub mods: Vec<ScriptConflictMod>,
+}
+
+impl ScriptConflict {
+  pub fn overrides_vanilla(&self) -> bool {
+    self.vanilla_path.is_some()
+  }
+}
+
+pub struct ScriptConflictReport {
+  pub conflicts: Vec<ScriptConflict>,
+
+  /// the number of mods with at least one script in the effective mods set
+  pub script_mods: usize,
+
+  /// the number of scripts the mods contain, contested or not
+  pub scripts: usize,
+}
+
+/// whether the mod is the output of a merge, which contains by definition the
+/// scripts of the other mods.
+pub fn is_mergedfiles(mod_name: &str) -> bool {
+  let mod_name = mod_name.to_lowercase();
+
+  mod_name == constants::SCRIPTMERGER_MERGEDFILES_FOLDERNAME.to_lowercase()
+    || mod_name.ends_with("_mergedfiles")
+}
+
+/// replaces the mods of an imported modlist with the merge of that modlist when
+/// it merged the script already, so the merges of the imports are reused
+/// instead of being merged again. The merge takes the place of the first of
+/// the mods it replaces.
+fn reuse_imported_merges(mut conflict: ScriptConflict, modlist_name: &str) -> ScriptConflict {
+  let mut merges: HashMap<String, Option<PathBuf>> = HashMap::new();
+  let mut mods = Vec::with_capacity(conflict.mods.len());
+
+  for script_mod in std::mem::take(&mut conflict.mods) {
+    if script_mod.modlist == modlist_name {
+      mods.push(script_mod);
+      continue;
+    }
+
+    let is_first = !merges.contains_key(&script_mod.modlist);
+    let merged_script = merges
+      .entry(script_mod.modlist.clone())
+      .or_insert_with(|| {
+        let merged_script = ModList::get_by_name(&script_mod.modlist)?
+          .own_merge_path()
+          .join("content")
+          .join("scripts");
+
+        let merged_script = conflict
+          .script
+          .split('/')
+          .fold(merged_script, |path, component| path.join(component));
+
+        merged_script.is_file().then_some(merged_script)
+      })
+      .clone();
+
+    match merged_script {
+      Some(path) if is_first => mods.push(ScriptConflictMod {
+        mod_name: constants::MODLIST_OWN_MERGE_NAME.to_owned(),
+        modlist: script_mod.modlist,
+        path,
+      }),
+      Some(_) => {}
+      None => mods.push(script_mod),
+    }
+  }
+
+  conflict.mods = mods;
+  conflict
+}
+
+/// lists the `.ws` files that are in more than one mod of the modlist, with the
+/// mods from the imports included. The scripts are compared without their
+/// case since the game doesn't care about it either.
+pub fn analyze(modlist_name: &str) -> std::io::Result<ScriptConflictReport> {
+  let EffectiveEntries {
+    index,
+    entries: effective_entries,
+    ..
+  } = open_effective_entries(modlist_name)?;
+
+  // the game loads the mods in alphabetical order, the case is ignored
+  let mut mods = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~') && !is_mergedfiles(&entry.name))
+    .collect::<Vec<_>>();
+
+  mods.sort_by_key(|entry| entry.name.to_lowercase());
+
+  let contents = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Content)
+    .map(|entry| (entry.modlist.as_str(), entry.name.as_str()))
+    .collect::<HashSet<_>>();
+
+  let mut vanilla_scripts = HashMap::new();
+  let sources = contents
+    .iter()
+    .map(|(source, _)| *source)
+    .collect::<HashSet<_>>();
+
+  for source in sources {
+    for file in index.get_files(source)? {
+      if file.category != EntryCategory::Content
+        || !contents.contains(&(source, file.entry.as_str()))
+      {
+        continue;
+      }
+
+      let script = format!("{}/{}", file.entry, file.path).to_lowercase();
+      vanilla_scripts.insert(script, (source.to_owned(), file.disk_path()));
+    }
+  }
+
+  // the files of every modlist the mods come from, read once per modlist
+  let mut files_by_modlist = BTreeMap::new();
+  for entry in &mods {
+    if !files_by_modlist.contains_key(entry.modlist.as_str()) {
+      files_by_modlist.insert(entry.modlist.as_str(), index.get_files(&entry.modlist)?);
+    }
+  }
+
+  let mut scripts: BTreeMap<String, ScriptConflict> = BTreeMap::new();
+  let mut script_mods = 0;
+
+  for entry in &mods {
+    let files = files_by_modlist
+      .get(entry.modlist.as_str())
+      .map(|files| files.as_slice())
+      .unwrap_or_default();
+
+    let mut has_scripts = false;
+
+    for file in files {
+      if file.category != EntryCategory::Mod || file.entry != entry.name {
+        continue;
+      }
+
+      let lowercase_path = file.path.to_lowercase();
+
+      if !lowercase_path.starts_with(MOD_SCRIPTS_PREFIX) || !lowercase_path.ends_with(".ws") {
+        continue;
+      }
+
+      has_scripts = true;
+
+      let script = &file.path[MOD_SCRIPTS_PREFIX.len()..];
+      let key = script.to_lowercase();
+
+      let conflict = scripts
+        .entry(key.clone())
+        .or_insert_with(|| ScriptConflict {
+          script: script.to_owned(),
+          vanilla_path: vanilla_scripts.get(&key).map(|(_, path)| path.clone()),
+          vanilla_modlist: vanilla_scripts.get(&key).map(|(source, _)| source.clone()),
+          mods: Vec::new(),
+        });
+
+      conflict.mods.push(ScriptConflictMod {
+        mod_name: entry.name.clone(),
+        modlist: entry.modlist.clone(),
+        path: file.disk_path(),
+      });
+    }
+
+    if has_scripts {
+      script_mods += 1;
+    }
+  }
+
+  let scripts_count = scripts.len();
+  let conflicts = scripts
+    .into_values()
+    .map(|conflict| reuse_imported_merges(conflict, modlist_name))
+    .filter(|conflict| conflict.mods.len() > 1)
+    .collect();
+
+  Ok(ScriptConflictReport {
+    conflicts,
+    script_mods,
+    scripts: scripts_count,
+  })
+}
@@ -0,0 +1,917 @@
+use encoding_rs_io::DecodeReaderBytes;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::constants;
+use crate::models::merge_manifest::{MergeManifest, MergeSource, MergedScript};
+use crate::models::merge_resolutions::{hunk_hash, MergeResolution, MergeResolutions};
+use crate::models::modlist::ModList;
+use crate::models::script_conflicts::{self, ScriptConflict, ScriptConflictMod};
+use crate::utils::diff3::{
+  has_crlf, merge3, normalize_line_endings, restore_line_endings, Merge3Chunk,
+};
+use crate::utils::hash::hash_bytes;
+use crate::utils::symlinks::make_symlink;
+
+/// the number of lines shown before and after a conflict
+const CONTEXT_LINES: usize = 3;
+
+const CONFLICT_START: &str = "<<<<<<< ours";
+const ORIGINAL_START: &str = "||||||| original";
+const ORIGINAL_END: &str = "=======";
+const CONFLICT_END: &str = ">>>>>>> theirs";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Conflict {
+  /// identifies the conflict in the resolutions. It is derived from the range
+  /// and the content of the conflict, so a resolution can't be applied to a
+  /// file that changed since the conflict was sent.
+  pub id: String,
+
+  /// the byte range of the file the resolution replaces, the contexts included
+  pub start: usize,
+  pub end: usize,
+
+  pub ours: String,
+  pub original: String,
+  pub theirs: String,
+
+  // some of the code before and after the conflict
+  pub context_before: String,
+  pub context_after: String,
+}
+
+impl Conflict {
+  /// the text that replaces the conflict in the file, made of the versions the
+  /// user accepted and the contexts.
+  fn resolved_text(&self) -> String {
+    format!(
+      "{}{}{}{}{}",
+      self.context_before, self.ours, self.original, self.theirs, self.context_after
+    )
+  }
+}
+
+/// a conflict that was resolved with the resolution the user gave to the same
+/// conflict in a previous merge.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutoResolvedConflict {
+  pub script: String,
+  pub mod_name: String,
+  pub hunk: String,
+}
+
+/// how far the merge is, sent to the merge page while the scripts are merged
+/// and along with the conflicts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MergeProgress {
+  /// the number of contested scripts the merge goes through
+  pub scripts: usize,
+
+  /// the number of scripts that are completely merged
+  pub scanned: usize,
+
+  /// the scripts that were merged without a single conflict
+  pub merged_cleanly: usize,
+
+  /// the conflicts waiting for resolutions
+  pub conflicts_pending: usize,
+
+  /// the script being merged, empty once they are all merged
+  pub current_file: String,
+
+  /// the conflicts resolved with remembered resolutions so far
+  #[serde(default)]
+  pub auto_resolved: Vec<AutoResolvedConflict>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SocketMessage {
+  pub conflicts: Vec<Conflict>,
+  pub file_name: String,
+  pub file_path: String,
+
+  pub mod_name: String,
+
+  /// set when the resolutions sent by the merge page could not be applied, the
+  /// conflicts stay the same and can be resolved again.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+
+  /// set on the messages sent while the merge goes on, a message with a
+  /// progress but no conflicts only updates the progress of the page.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub progress: Option<MergeProgress>,
+
+  /// set when the merge was cancelled and the previous merged scripts restored
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub cancelled: bool,
+}
+
+impl SocketMessage {
+  /// the message sent once every script is merged, the merge page stops when
+  /// it receives a message without conflicts.
+  pub fn finished() -> SocketMessage {
+    SocketMessage {
+      conflicts: Vec::new(),
+      file_name: String::new(),
+      file_path: String::new(),
+      mod_name: String::new(),
+      error: None,
+      progress: None,
+      cancelled: false,
+    }
+  }
+
+  pub fn error(error: String) -> SocketMessage {
+    SocketMessage {
+      error: Some(error),
+      ..SocketMessage::finished()
+    }
+  }
+
+  pub fn progress(progress: MergeProgress) -> SocketMessage {
+    SocketMessage {
+      progress: Some(progress),
+      ..SocketMessage::finished()
+    }
+  }
+
+  pub fn cancelled() -> SocketMessage {
+    SocketMessage {
+      cancelled: true,
+      ..SocketMessage::finished()
+    }
+  }
+}
+
+/// reads the script whatever its encoding is, the vanilla scripts are in UTF-16
+/// while most mods use UTF-8.
+pub fn read_script(path: &PathBuf) -> std::io::Result<String> {
+  let source_data = fs::read(path)?;
+  let mut decoder = DecodeReaderBytes::new(&source_data[..]);
+
+  let mut dest = String::new();
+  decoder.read_to_string(&mut dest)?;
+
+  Ok(dest)
+}
+
+fn push_section(output: &mut String, lines: &[&str]) {
+  for line in lines {
+    output.push_str(line);
+  }
+
+  // the markers must start on their own line
+  if !output.is_empty() && !output.ends_with('\n') {
+    output.push('\n');
+  }
+}
+
+/// returns the byte offset where the last `count` lines of the text start,
+/// without going before `limit`.
+fn last_lines_start(text: &str, count: usize, limit: usize) -> usize {
+  let mut start = text.len();
+
+  for _ in 0..count {
+    if start <= limit {
+      break;
+    }
+
+    start = text[..start - 1].rfind('\n').map(|i| i + 1).unwrap_or(0);
+  }
+
+  start.max(limit)
+}
+
+/// returns the byte offset where the first `count` lines of the text end
+fn first_lines_end(text: &str, count: usize) -> usize {
+  let mut end = 0;
+
+  for _ in 0..count {
+    match text[end..].find('\n') {
+      Some(i) => end += i + 1,
+      None => return text.len(),
+    }
+  }
+
+  end
+}
+
+fn conflict_id(start: usize, region: &str) -> String {
+  hash_bytes(format!("{}:{}", start, region).as_bytes())
+}
+
+/// writes the chunks of a merge into a single text with the conflicts between
+/// diff3 markers, and returns the conflicts in the form the merge page expects.
+fn render_merge(chunks: &[Merge3Chunk]) -> (String, Vec<Conflict>) {
+  let mut output = String::new();
+
+  // for every conflict: the start of its first marker, the end of its last
+  // marker line and its three versions.
+  let mut blocks = Vec::new();
+
+  for chunk in chunks {
+    match chunk {
+      Merge3Chunk::Resolved(lines) => {
+        for line in lines {
+          output.push_str(line);
+        }
+      }
+      Merge3Chunk::Conflict {
+        original,
+        ours,
+        theirs,
+      } => {
+        push_section(&mut output, &[]);
+
+        let marker_start = output.len();
+
+        output.push_str(CONFLICT_START);
+        output.push('\n');
+        push_section(&mut output, ours);
+        output.push_str(ORIGINAL_START);
+        output.push('\n');
+        push_section(&mut output, original);
+        output.push_str(ORIGINAL_END);
+        output.push('\n');
+        push_section(&mut output, theirs);
+        output.push_str(CONFLICT_END);
+        output.push('\n');
+
+        blocks.push((
+          marker_start,
+          output.len(),
+          ours.concat(),
+          original.concat(),
+          theirs.concat(),
+        ));
+      }
+    }
+  }
+
+  let mut conflicts = Vec::new();
+
+  // the contexts stop at the neighbouring conflicts so the ranges of two
+  // conflicts never overlap.
+  let mut previous_end = 0;
+
+  for (i, (marker_start, marker_end, ours, original, theirs)) in blocks.iter().enumerate() {
+    let next_start = blocks
+      .get(i + 1)
+      .map(|(next_marker, ..)| *next_marker)
+      .unwrap_or(output.len());
+
+    let start = last_lines_start(&output[..*marker_start], CONTEXT_LINES, previous_end);
+    let end = marker_end + first_lines_end(&output[*marker_end..next_start], CONTEXT_LINES);
+
+    conflicts.push(Conflict {
+      id: conflict_id(start, &output[start..end]),
+      start,
+      end,
+      ours: ours.clone(),
+      original: original.clone(),
+      theirs: theirs.clone(),
+      context_before: output[start..*marker_start].to_owned(),
+      context_after: output[*marker_end..end].to_owned(),
+    });
+
+    previous_end = end;
+  }
+
+  (output, conflicts)
+}
+
+/// replaces the conflicts in the content with their resolutions. Every conflict
+/// needs a resolution, and the content must be the same as when the conflicts
+/// were sent.
+pub fn apply_resolutions(
+  content: &str, conflicts: &[Conflict], resolutions: &[Conflict],
+) -> Result<String, String> {
+  if let Some(unknown) = resolutions.iter().find(|resolution| {
+    !conflicts
+      .iter()
+      .any(|conflict| conflict.id == resolution.id)
+  }) {
+    return Err(format!("the resolution {} matches no conflict", unknown.id));
+  }
+
+  let mut replacements = Vec::new();
+
+  for conflict in conflicts {
+    let resolution = resolutions
+      .iter()
+      .find(|resolution| resolution.id == conflict.id)
+      .ok_or_else(|| format!("the conflict {} has no resolution", conflict.id))?;
+
+    if resolution.start != conflict.start || resolution.end != conflict.end {
+      return Err(format!(
+        "the range of the resolution {} is not the range of its conflict",
+        conflict.id
+      ));
+    }
+
+    let region = content
+      .get(conflict.start..conflict.end)
+      .ok_or_else(|| format!("the conflict {} is outside of the file", conflict.id))?;
+
+    if conflict_id(conflict.start, region) != conflict.id {
+      return Err(format!(
+        "the file changed since the conflict {} was found",
+        conflict.id
+      ));
+    }
+
+    replacements.push((conflict.start, conflict.end, resolution.resolved_text()));
+  }
+
+  replacements.sort_by_key(|(start, _, _)| *start);
+
+  let mut output = String::with_capacity(content.len());
+  let mut cursor = 0;
+
+  for (start, end, text) in replacements {
+    if start < cursor {
+      return Err(String::from("two conflicts overlap"));
+    }
+
+    output.push_str(&content[cursor..start]);
+    output.push_str(&text);
+    cursor = end;
+  }
+
+  output.push_str(&content[cursor..]);
+
+  Ok(output)
+}
+
+/// replaces the conflicts that have a remembered resolution, and returns the
+/// content along with the conflicts that remain. The ranges and ids of the
+/// remaining conflicts are those of the returned content.
+fn apply_remembered(
+  content: &str, conflicts: &[Conflict], script: &str, mod_name: &str,
+  remembered: &MergeResolutions,
+) -> (String, Vec<Conflict>, Vec<AutoResolvedConflict>) {
+  let mut output = String::with_capacity(content.len());
+  let mut remaining = Vec::new();
+  let mut auto_resolved = Vec::new();
+  let mut cursor = 0;
+
+  for conflict in conflicts {
+    let region = &content[conflict.start..conflict.end];
+    let hunk = hunk_hash(region);
+
+    output.push_str(&content[cursor..conflict.start]);
+    cursor = conflict.end;
+
+    match remembered.find(script, mod_name, &hunk) {
+      Some(resolution) => {
+        output.push_str(&resolution.resolution);
+
+        auto_resolved.push(AutoResolvedConflict {
+          script: script.to_owned(),
+          mod_name: mod_name.to_owned(),
+          hunk,
+        });
+      }
+      None => {
+        let start = output.len();
+        output.push_str(region);
+
+        remaining.push(Conflict {
+          id: conflict_id(start, region),
+          start,
+          end: output.len(),
+          ..conflict.clone()
+        });
+      }
+    }
+  }
+
+  output.push_str(&content[cursor..]);
+
+  (output, remaining, auto_resolved)
+}
+
+pub enum MergeStep {
+  /// a mod was merged into the output without conflicts
+  Merged,
+
+  /// a mod was merged into the output but some of its changes conflict with
+  /// the previous mods, the output must be resolved before the next step.
+  Conflicted(Box<SocketMessage>),
+
+  /// every mod was merged into the output
+  Finished,
+}
+
+/// the merge of a single script. The script of the first mod is the starting
+/// point, then the scripts of the other mods are merged into it one after the
+/// other with the vanilla script as the common ancestor.
+pub struct ScriptMerge {
+  pub script: String,
+  pub output_path: PathBuf,
+
+  /// the texts are merged with `\n` line endings, the output gets the line
+  /// endings of the vanilla script back, or those of the first mod.
+  crlf: bool,
+
+  original: String,
+  vanilla_path: Option<PathBuf>,
+  vanilla_modlist: String,
+  merged: Option<String>,
+  pending_mods: VecDeque<ScriptConflictMod>,
+
+  /// the conflicts of the last step, until they are resolved
+  conflicts: Vec<Conflict>,
+
+  /// whether one of the steps had conflicts, resolved or not
+  had_conflicts: bool,
+
+  /// every mod that has the script, in the order they're merged
+  mods: Vec<ScriptConflictMod>,
+
+  /// the mod merged by the last step
+  current_mod: String,
+
+  /// the conflicts that were resolved with remembered resolutions
+  auto_resolved: Vec<AutoResolvedConflict>,
+}
+
+impl ScriptMerge {
+  pub fn new(conflict: &ScriptConflict, output_folder: &Path) -> std::io::Result<ScriptMerge> {
+    // a script no vanilla file has is merged as if every mod added it to an
+    // empty file.
+    let original = match &conflict.vanilla_path {
+      Some(path) => read_script(path)?,
+      None => String::new(),
+    };
+    let crlf = has_crlf(&original);
+
+    let output_path = conflict
+      .script
+      .split('/')
+      .fold(output_folder.to_path_buf(), |path, component| {
+        path.join(component)
+      });
+
+    Ok(ScriptMerge {
+      script: conflict.script.clone(),
+      output_path,
+      crlf,
+      original: normalize_line_endings(&original),
+      vanilla_path: conflict.vanilla_path.clone(),
+      vanilla_modlist: conflict.vanilla_modlist.clone().unwrap_or_default(),
+      merged: None,
+      pending_mods: conflict.mods.iter().cloned().collect(),
+      conflicts: Vec::new(),
+      had_conflicts: false,
+      mods: conflict.mods.clone(),
+      current_mod: String::new(),
+      auto_resolved: Vec::new(),
+    })
+  }
+
+  fn write_output(&self, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = self.output_path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    fs::write(
+      &self.output_path,
+      restore_line_endings(content.to_owned(), self.crlf),
+    )
+  }
+
+  /// merges the next mod into the output, the conflicts the user already
+  /// resolved in a previous merge are resolved the same way.
+  pub fn step(&mut self, remembered: &MergeResolutions) -> std::io::Result<MergeStep> {
+    if !self.conflicts.is_empty() {
+      return Err(std::io::Error::other(
+        "the conflicts of the previous mod are not resolved",
+      ));
+    }
+
+    let script_mod = match self.pending_mods.pop_front() {
+      Some(script_mod) => script_mod,
+      None => return Ok(MergeStep::Finished),
+    };
+
+    let theirs = read_script(&script_mod.path)?;
+    self.current_mod = script_mod.mod_name.clone();
+
+    let merged = match &self.merged {
+      Some(merged) => merged,
+      None => {
+        if self.vanilla_path.is_none() {
+          self.crlf = has_crlf(&theirs);
+        }
+
+        let theirs = normalize_line_endings(&theirs);
+
+        self.write_output(&theirs)?;
+        self.merged = Some(theirs);
+
+        return Ok(MergeStep::Merged);
+      }
+    };
+
+    let theirs = normalize_line_endings(&theirs);
+    let chunks = merge3(&self.original, merged, &theirs);
+    let (output, conflicts) = render_merge(&chunks);
+    let (output, conflicts, auto_resolved) = apply_remembered(
+      &output,
+      &conflicts,
+      &self.script,
+      &script_mod.mod_name,
+      remembered,
+    );
+
+    if !auto_resolved.is_empty() {
+      self.had_conflicts = true;
+      self.auto_resolved.extend(auto_resolved);
+    }
+
+    self.write_output(&output)?;
+    self.merged = Some(output);
+
+    if conflicts.is_empty() {
+      return Ok(MergeStep::Merged);
+    }
+
+    self.conflicts = conflicts.clone();
+    self.had_conflicts = true;
+
+    Ok(MergeStep::Conflicted(Box::new(SocketMessage {
+      conflicts,
+      file_name: self.script.clone(),
+      file_path: self.output_path.to_string_lossy().to_string(),
+      mod_name: script_mod.mod_name,
+      error: None,
+      progress: None,
+      cancelled: false,
+    })))
+  }
+
+  /// applies the resolutions to the conflicts of the last step, so the next
+  /// mods are merged into the resolved version. Nothing is written when one of
+  /// the resolutions is invalid. Returns the resolutions to remember for the
+  /// next merges.
+  pub fn resolve(&mut self, resolutions: &[Conflict]) -> Result<Vec<MergeResolution>, String> {
+    // the offsets of the conflicts are in the merged text, which has `\n` line
+    // endings
+    let content = read_script(&self.output_path)
+      .map(|content| normalize_line_endings(&content))
+      .map_err(|err| format!("could not read {}. {}", self.script, err))?;
+
+    let resolved = apply_resolutions(&content, &self.conflicts, resolutions)?;
+
+    // the resolutions were validated, every conflict has one
+    let remembered = self
+      .conflicts
+      .iter()
+      .filter_map(|conflict| {
+        let resolution = resolutions
+          .iter()
+          .find(|resolution| resolution.id == conflict.id)?;
+        let region = &content[conflict.start..conflict.end];
+
+        Some(MergeResolution {
+          script: self.script.clone(),
+          mod_name: self.current_mod.clone(),
+          hunk: hunk_hash(region),
+          conflict: region.to_owned(),
+          resolution: resolution.resolved_text(),
+        })
+      })
+      .collect();
+
+    self
+      .write_output(&resolved)
+      .map_err(|err| format!("could not write {}. {}", self.script, err))?;
+
+    self.merged = Some(resolved);
+    self.conflicts.clear();
+
+    Ok(remembered)
+  }
+}
+
+/// merges every script that is in more than one mod of the modlist. The
+/// scripts are merged in a staging folder of the modlist and only replace the
+/// scripts of the modlist's merge once every conflict is resolved, the previous
+/// merged scripts are kept as a backup.
+pub struct ScriptMerger {
+  merges: Vec<ScriptMerge>,
+  current: usize,
+
+  merges_path: PathBuf,
+  merge_path: PathBuf,
+  staging_path: PathBuf,
+  backup_path: PathBuf,
+
+  modlist: ModList,
+  resolutions: MergeResolutions,
+}
+
+impl ScriptMerger {
+  pub fn new(modlist: &ModList) -> std::io::Result<ScriptMerger> {
+    let report = script_conflicts::analyze(&modlist.name)?;
+
+    let staging_path = modlist.merge_staging_path();
+
+    // the leftover of a merge that was interrupted
+    if staging_path.exists() {
+      fs::remove_dir_all(&staging_path)?;
+    }
+
+    let output_folder = staging_path.join("scripts");
+
+    let merges = report
+      .conflicts
+      .iter()
+      .map(|conflict| ScriptMerge::new(conflict, &output_folder))
+      .collect::<std::io::Result<Vec<ScriptMerge>>>()?;
+
+    fs::create_dir_all(&output_folder)?;
+
+    Ok(ScriptMerger {
+      merges,
+      current: 0,
+      merges_path: modlist.merges_path(),
+      merge_path: modlist.own_merge_path(),
+      staging_path,
+      backup_path: modlist.mergedscripts_backup_path(),
+      modlist: modlist.clone(),
+      resolutions: MergeResolutions::read_from_disk(modlist)?,
+    })
+  }
+
+  pub fn progress(&self) -> MergeProgress {
+    let merged_cleanly = self.merges[..self.current]
+      .iter()
+      .filter(|merge| !merge.had_conflicts)
+      .count();
+
+    let current = self.merges.get(self.current);
+
+    MergeProgress {
+      scripts: self.merges.len(),
+      scanned: self.current,
+      merged_cleanly,
+      conflicts_pending: current.map(|merge| merge.conflicts.len()).unwrap_or(0),
+      current_file: current
+        .map(|merge| merge.script.clone())
+        .unwrap_or_default(),
+      auto_resolved: self
+        .merges
+        .iter()
+        .flat_map(|merge| merge.auto_resolved.iter().cloned())
+        .collect(),
+    }
+  }
+
+  /// merges the scripts until one of them has conflicts. Returns `None` once
+  /// every script is merged, or as soon as `cancelled` is set. The progress is
+  /// reported before every script.
+  pub fn next_conflict(
+    &mut self, cancelled: &AtomicBool, mut on_progress: impl FnMut(MergeProgress),
+  ) -> std::io::Result<Option<SocketMessage>> {
+    let mut reported = None;
+
+    while self.current < self.merges.len() {
+      if cancelled.load(Ordering::SeqCst) {
+        return Ok(None);
+      }
+
+      if reported != Some(self.current) {
+        reported = Some(self.current);
+        on_progress(self.progress());
+      }
+
+      let merge = &mut self.merges[self.current];
+      match merge.step(&self.resolutions)? {
+        MergeStep::Merged => {}
+        MergeStep::Conflicted(mut message) => {
+          message.progress = Some(self.progress());
+
+          return Ok(Some(*message));
+        }
+        MergeStep::Finished => self.current += 1,
+      }
+    }
+
+    on_progress(self.progress());
+
+    // the last step can take a while, the merge may have been cancelled during
+    // it and nothing must replace the current merge then.
+    if cancelled.load(Ordering::SeqCst) {
+      return Ok(None);
+    }
+
+    swap_merged_scripts(
+      &self.merge_path,
+      &self.staging_path.join("scripts"),
+      &self.backup_path,
+    )?;
+
+    fs::remove_dir_all(&self.staging_path)?;
+
+    record_merged_files(&self.merges_path, &self.merges)?;
+    self.write_manifest()?;
+    self.modlist.link_own_merge()?;
+
+    Ok(None)
+  }
+
+  /// records the files every script was merged from with their hashes, to know
+  /// later when the merge is outdated.
+  fn write_manifest(&self) -> std::io::Result<()> {
+    let mut manifest = MergeManifest {
+      merged_at: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+      scripts: Vec::new(),
+    };
+
+    for merge in &self.merges {
+      let original = match &merge.vanilla_path {
+        Some(path) => Some(MergeSource::from_path(
+          "content",
+          &merge.vanilla_modlist,
+          path,
+        )?),
+        None => None,
+      };
+
+      let sources = merge
+        .mods
+        .iter()
+        .map(|script_mod| {
+          MergeSource::from_path(&script_mod.mod_name, &script_mod.modlist, &script_mod.path)
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+      manifest.scripts.push(MergedScript {
+        script: merge.script.clone(),
+        original,
+        sources,
+      });
+    }
+
+    manifest
+      .write_to_disk(&self.modlist)
+      .map_err(std::io::Error::other)
+  }
+
+  /// stops the merge, the merged scripts of the modlist were never touched so
+  /// only the staging folder is removed.
+  pub fn cancel(&mut self) -> std::io::Result<()> {
+    self.merges.clear();
+    self.current = 0;
+
+    if self.staging_path.exists() {
+      fs::remove_dir_all(&self.staging_path)?;
+    }
+
+    Ok(())
+  }
+
+  /// applies the resolutions of the conflicts returned by `next_conflict`
+  pub fn resolve(&mut self, resolutions: &SocketMessage) -> Result<(), String> {
+    let merge = self
+      .merges
+      .get_mut(self.current)
+      .ok_or_else(|| String::from("there is no conflict to resolve"))?;
+
+    if merge.script != resolutions.file_name {
+      return Err(format!(
+        "the resolutions are for {} but the conflicts are in {}",
+        resolutions.file_name, merge.script
+      ));
+    }
+
+    let remembered = merge.resolve(&resolutions.conflicts)?;
+
+    for resolution in remembered {
+      self.resolutions.remember(resolution);
+    }
+
+    // the conflicts are resolved anyway, they will only be asked again in the
+    // next merge.
+    if let Err(error) = self.resolutions.write_to_disk(&self.modlist) {
+      println!("could not remember the resolutions: {}", error);
+    }
+
+    Ok(())
+  }
+}
+
+/// moves the merged scripts of the merge to `backup_path` and the scripts in
+/// `scripts_path` in their place. The previous backup is replaced.
+fn swap_merged_scripts(
+  merge_path: &Path, scripts_path: &Path, backup_path: &Path,
+) -> std::io::Result<()> {
+  let output_folder = merge_path.join("content").join("scripts");
+
+  let has_backup = if output_folder.exists() {
+    if backup_path.exists() {
+      fs::remove_dir_all(backup_path)?;
+    }
+
+    fs::rename(&output_folder, backup_path)?;
+
+    true
+  } else {
+    false
+  };
+
+  let result = fs::create_dir_all(merge_path.join("content"))
+    .and_then(|_| fs::rename(scripts_path, &output_folder));
+
+  // the previous scripts are put back where they were so the merge is never
+  // left without its scripts.
+  if let Err(error) = result {
+    if has_backup {
+      fs::rename(backup_path, &output_folder)?;
+    }
+
+    return Err(error);
+  }
+
+  Ok(())
+}
+
+/// replaces the folders of the mods in `merges/` with symlinks to the scripts
+/// of the last merge, so the folder tells which file of which mod is in the
+/// merge.
+fn record_merged_files(merges_path: &PathBuf, merges: &[ScriptMerge]) -> std::io::Result<()> {
+  if merges_path.exists() {
+    for child in fs::read_dir(merges_path)? {
+      let child = child?;
+
+      if child.file_name() == constants::MODLIST_OWN_MERGE_NAME || !child.file_type()?.is_dir() {
+        continue;
+      }
+
+      fs::remove_dir_all(child.path())?;
+    }
+  }
+
+  for merge in merges {
+    for script_mod in &merge.mods {
+      let link_path = merge.script.split('/').fold(
+        merges_path
+          .join(&script_mod.mod_name)
+          .join("content")
+          .join("scripts"),
+        |path, component| path.join(component),
+      );
+
+      if let Some(parent) = link_path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+
+      make_symlink(&link_path, &script_mod.path)?;
+    }
+  }
+
+  Ok(())
+}
+
+/// whether the modlist has merged scripts from a previous merge to restore
+pub fn has_merged_scripts_backup(modlist: &ModList) -> bool {
+  modlist.mergedscripts_backup_path().is_dir()
+}
+
+/// puts the merged scripts of the previous merge back in the merge, the
+/// current ones become the backup so it can be undone the same way.
+pub fn restore_merged_scripts_backup(modlist: &ModList) -> std::io::Result<()> {
+  let backup_path = modlist.mergedscripts_backup_path();
+
+  if !backup_path.is_dir() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::NotFound,
+      "there is no backup of the merged scripts",
+    ));
+  }
+
+  // the backup is moved aside first, since the swap replaces it with the
+  // current scripts.
+  let restored_path = modlist.merge_staging_path().join("restored");
+  if let Some(parent) = restored_path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  fs::rename(&backup_path, &restored_path)?;
+
+  if let Err(error) = swap_merged_scripts(&modlist.own_merge_path(), &restored_path, &backup_path) {
+    fs::rename(&restored_path, &backup_path)?;
+
+    return Err(error);
+  }
+
+  fs::remove_dir_all(modlist.merge_staging_path())
+}
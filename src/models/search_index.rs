@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::database_index::{
+  get_effective_entries, get_load_order, DatabaseIndex, EntryCategory,
+};
+use crate::models::modlist::ModList;
+
+/// the maximum number of hits a search returns, so a query like `.ws` doesn't
+/// render a page with every script of the database.
+pub const MAX_SEARCH_HITS: usize = 500;
+
+/// a file, or a top level folder, of a modlist
+#[derive(Clone, Debug)]
+pub struct SearchEntry {
+  pub category: EntryCategory,
+
+  /// the name of the direct child of the category folder the entry is in, for
+  /// example the name of the mod.
+  pub entry: String,
+
+  /// the path of the file relative to the entry, with `/` as the separator.
+  /// It is empty for the entry itself.
+  pub path: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+  pub modlist: String,
+  pub entry: SearchEntry,
+
+  /// the modlist the entry comes from when it is not local to `modlist`
+  pub imported_from: Option<String>,
+}
+
+pub struct SearchResults {
+  pub hits: Vec<SearchHit>,
+
+  /// whether there were more than `MAX_SEARCH_HITS` hits
+  pub truncated: bool,
+}
+
+/// finds the mods, dlcs, menus and files whose path contains the query, in
+/// every modlist of the database. The index is refreshed before the search,
+/// `force_refresh` walks every modlist again rather than only the ones whose
+/// folders changed.
+pub fn search(query: &str, force_refresh: bool) -> std::io::Result<SearchResults> {
+  let query = query.trim().replace('\\', "/").to_lowercase();
+
+  let mut modlists = ModList::get_all();
+  modlists.sort_by(|a, b| a.name.cmp(&b.name));
+
+  for modlist in &mut modlists {
+    modlist.read_metadata_from_disk()?;
+  }
+
+  let mut index = DatabaseIndex::open()?;
+  index.refresh(&modlists, force_refresh)?;
+
+  let mut hits = Vec::new();
+  let mut truncated = false;
+
+  if query.is_empty() {
+    return Ok(SearchResults { hits, truncated });
+  }
+
+  let entries = index.get_all_entries()?;
+
+  // the matching entries and files of each modlist, without their imports.
+  // The entries come before their files since their path is empty.
+  let mut matches: HashMap<String, Vec<SearchEntry>> = HashMap::new();
+
+  for entry in &entries {
+    if entry.name.to_lowercase().contains(&query) {
+      matches
+        .entry(entry.modlist.clone())
+        .or_default()
+        .push(SearchEntry {
+          category: entry.category,
+          entry: entry.name.clone(),
+          path: String::new(),
+        });
+    }
+  }
+
+  for file in index.find_files(&query)? {
+    // the files directly in the category folders are their own entry
+    if file.path.is_empty() {
+      continue;
+    }
+
+    matches
+      .entry(file.modlist.clone())
+      .or_default()
+      .push(SearchEntry {
+        category: file.category,
+        entry: file.entry,
+        path: file.path,
+      });
+  }
+
+  for modlist_matches in matches.values_mut() {
+    modlist_matches.sort_by(|a, b| {
+      (a.category.as_str(), &a.entry, &a.path).cmp(&(b.category.as_str(), &b.entry, &b.path))
+    });
+  }
+
+  'modlists: for modlist in &modlists {
+    let load_order = get_load_order(modlist, &modlists);
+
+    let owners = get_effective_entries(modlist, &modlists, &entries)
+      .into_iter()
+      .map(|entry| (entry.category, entry.name.as_str(), entry.modlist.as_str()))
+      .collect::<HashSet<_>>();
+
+    for source in &load_order {
+      let is_local = source == &modlist.name;
+
+      for entry in matches.get(source).into_iter().flatten() {
+        if !owners.contains(&(entry.category, entry.entry.as_str(), source.as_str())) {
+          continue;
+        }
+
+        if hits.len() >= MAX_SEARCH_HITS {
+          truncated = true;
+          break 'modlists;
+        }
+
+        hits.push(SearchHit {
+          modlist: modlist.name.clone(),
+          entry: entry.clone(),
+          imported_from: if is_local { None } else { Some(source.clone()) },
+        });
+      }
+    }
+  }
+
+  Ok(SearchResults { hits, truncated })
+}
@@ -0,0 +1,145 @@
+/// the lines of a settings file of the game, like input.settings or
+/// user.settings, grouped by `[Context]` section in the order of the file. The
+/// lines above the first section are in a section with an empty name.
+pub struct SettingsFile {
+  pub sections: Vec<(String, Vec<String>)>,
+}
+
+impl SettingsFile {
+  pub fn parse(text: &str) -> SettingsFile {
+    let mut sections: Vec<(String, Vec<String>)> = vec![(String::new(), Vec::new())];
+
+    for line in text.lines() {
+      let trimmed = line.trim();
+
+      if trimmed.starts_with('[') && trimmed.ends_with(']') {
+        let context = trimmed[1..trimmed.len() - 1].trim().to_owned();
+        sections.push((context, Vec::new()));
+      } else if let Some((_, lines)) = sections.last_mut() {
+        lines.push(line.trim_end().to_owned());
+      }
+    }
+
+    SettingsFile { sections }
+  }
+
+  fn section_mut(&mut self, context: &str) -> Option<&mut Vec<String>> {
+    self
+      .sections
+      .iter_mut()
+      .find(|(name, _)| name == context)
+      .map(|(_, lines)| lines)
+  }
+
+  /// adds the line at the end of the context, the context is created if the
+  /// file doesn't have it. Returns false if the context already has the line.
+  pub fn insert(&mut self, context: &str, line: &str) -> bool {
+    if self.section_mut(context).is_none() {
+      self.sections.push((context.to_owned(), Vec::new()));
+    }
+
+    let lines = match self.section_mut(context) {
+      Some(lines) => lines,
+      None => return false,
+    };
+
+    if lines.iter().any(|existing| existing.trim() == line.trim()) {
+      return false;
+    }
+
+    // the blank lines that separate the context from the next one stay at the
+    // end.
+    let position = lines
+      .iter()
+      .rposition(|existing| !existing.trim().is_empty())
+      .map(|position| position + 1)
+      .unwrap_or(0);
+
+    lines.insert(position, line.to_owned());
+
+    true
+  }
+
+  /// removes the line from the context, returns whether it was there. The
+  /// context goes away with its last line.
+  pub fn remove(&mut self, context: &str, line: &str) -> bool {
+    let lines = match self.section_mut(context) {
+      Some(lines) => lines,
+      None => return false,
+    };
+
+    let position = match lines
+      .iter()
+      .position(|existing| existing.trim() == line.trim())
+    {
+      Some(position) => position,
+      None => return false,
+    };
+
+    lines.remove(position);
+
+    if !context.is_empty() && lines.iter().all(|line| line.trim().is_empty()) {
+      self.sections.retain(|(name, _)| name != context);
+    }
+
+    true
+  }
+
+  /// the value of the `key=value` line of the context
+  pub fn get(&self, context: &str, key: &str) -> Option<&str> {
+    let (_, lines) = self.sections.iter().find(|(name, _)| name == context)?;
+
+    lines.iter().find_map(|line| {
+      let (line_key, value) = line.split_once('=')?;
+
+      (line_key.trim() == key).then(|| value.trim())
+    })
+  }
+
+  /// replaces the value of the `key=value` line of the context, or adds the
+  /// line if the context doesn't have it.
+  pub fn set(&mut self, context: &str, key: &str, value: &str) {
+    let line = format!("{}={}", key, value);
+
+    let existing = self.section_mut(context).and_then(|lines| {
+      lines.iter_mut().find(|line| {
+        line
+          .split_once('=')
+          .map(|(line_key, _)| line_key.trim() == key)
+          .unwrap_or(false)
+      })
+    });
+
+    match existing {
+      Some(existing) => *existing = line,
+      None => {
+        self.insert(context, &line);
+      }
+    }
+  }
+
+  /// removes the context and all its lines, returns whether it was there
+  pub fn remove_context(&mut self, context: &str) -> bool {
+    let count = self.sections.len();
+    self.sections.retain(|(name, _)| name != context);
+
+    self.sections.len() != count
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut text = String::new();
+
+    for (context, lines) in &self.sections {
+      if !context.is_empty() {
+        text.push_str(&format!("[{}]\r\n", context));
+      }
+
+      for line in lines {
+        text.push_str(line);
+        text.push_str("\r\n");
+      }
+    }
+
+    text
+  }
+}
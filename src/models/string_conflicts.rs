@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::models::database_index::{open_effective_entries, EffectiveEntries, EntryCategory};
+use crate::models::script_conflicts::is_mergedfiles;
+use crate::models::w3strings::read_w3strings;
+
+/// where the w3strings files are stored in a mod, relative to the mod folder
+const MOD_STRINGS_PREFIX: &str = "content/";
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StringCollisionMod {
+  pub mod_name: String,
+
+  /// the modlist the mod comes from, it is the analyzed modlist itself when the
+  /// mod is not imported.
+  pub modlist: String,
+}
+
+/// string ids that the same mods use for the same language
+#[derive(Clone, Debug)]
+pub struct StringCollision {
+  pub language: String,
+
+  /// the mods that use the ids, in the order the game loads them
+  pub mods: Vec<StringCollisionMod>,
+
+  /// the colliding ids, sorted
+  pub ids: Vec<u32>,
+
+  /// how many of the ids are of strings with a key, the scripts find them by
+  /// their key.
+  pub keyed: usize,
+}
+
+impl StringCollision {
+  /// the lowest and highest colliding ids
+  pub fn range(&self) -> (u32, u32) {
+    (
+      self.ids.first().cloned().unwrap_or_default(),
+      self.ids.last().cloned().unwrap_or_default(),
+    )
+  }
+}
+
+pub struct StringCollisionReport {
+  pub collisions: Vec<StringCollision>,
+
+  /// the number of mods with at least one w3strings file in the effective mods
+  /// set.
+  pub string_mods: usize,
+
+  /// the w3strings files that could not be read, with the reason
+  pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// lists the string ids more than one mod of the modlist uses for the same
+/// language, with the mods from the imports included. The ids are grouped by
+/// the mods that share them so a range two mods both use is a single
+/// collision.
+pub fn analyze(modlist_name: &str) -> std::io::Result<StringCollisionReport> {
+  let EffectiveEntries {
+    index,
+    entries: effective_entries,
+    ..
+  } = open_effective_entries(modlist_name)?;
+
+  // the game loads the mods in alphabetical order, the case is ignored
+  let mut mods = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~') && !is_mergedfiles(&entry.name))
+    .collect::<Vec<_>>();
+
+  mods.sort_by_key(|entry| entry.name.to_lowercase());
+
+  // the files of every modlist the mods come from, read once per modlist
+  let mut files_by_modlist = BTreeMap::new();
+  for entry in &mods {
+    if !files_by_modlist.contains_key(entry.modlist.as_str()) {
+      files_by_modlist.insert(entry.modlist.as_str(), index.get_files(&entry.modlist)?);
+    }
+  }
+
+  // the mods that use every id of every language, and whether it has a key
+  let mut ids: BTreeMap<(String, u32), (Vec<StringCollisionMod>, bool)> = BTreeMap::new();
+  let mut string_mods = 0;
+  let mut unreadable = Vec::new();
+
+  for entry in &mods {
+    let files = files_by_modlist
+      .get(entry.modlist.as_str())
+      .map(|files| files.as_slice())
+      .unwrap_or_default();
+
+    let mut has_strings = false;
+
+    for file in files {
+      if file.category != EntryCategory::Mod || file.entry != entry.name {
+        continue;
+      }
+
+      let lowercase_path = file.path.to_lowercase();
+
+      if !lowercase_path.starts_with(MOD_STRINGS_PREFIX)
+        || !lowercase_path.ends_with(".w3strings")
+        || lowercase_path[MOD_STRINGS_PREFIX.len()..].contains('/')
+      {
+        continue;
+      }
+
+      has_strings = true;
+
+      let strings = match read_w3strings(&file.disk_path()) {
+        Ok(strings) => strings,
+        Err(error) => {
+          unreadable.push((file.disk_path(), error.to_string()));
+          continue;
+        }
+      };
+
+      for string in strings.entries {
+        let (users, keyed) = ids
+          .entry((strings.language.clone(), string.id))
+          .or_insert_with(|| (Vec::new(), false));
+
+        *keyed |= string.key_hash.is_some();
+
+        // the mods are read in the load order, an id is listed once per mod
+        // even if the mod has it twice.
+        if users.last().map(|user| &user.mod_name) != Some(&entry.name) {
+          users.push(StringCollisionMod {
+            mod_name: entry.name.clone(),
+            modlist: entry.modlist.clone(),
+          });
+        }
+      }
+    }
+
+    if has_strings {
+      string_mods += 1;
+    }
+  }
+
+  let mut collisions: BTreeMap<(String, Vec<StringCollisionMod>), StringCollision> =
+    BTreeMap::new();
+
+  for ((language, id), (users, keyed)) in ids {
+    if users.len() < 2 {
+      continue;
+    }
+
+    let collision = collisions
+      .entry((language.clone(), users.clone()))
+      .or_insert_with(|| StringCollision {
+        language,
+        mods: users,
+        ids: Vec::new(),
+        keyed: 0,
+      });
+
+    collision.ids.push(id);
+    collision.keyed += keyed as usize;
+  }
+
+  Ok(StringCollisionReport {
+    collisions: collisions.into_values().collect(),
+    string_mods,
+    unreadable,
+  })
+}
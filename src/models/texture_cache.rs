@@ -0,0 +1,251 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// the size of the footer at the end of the cache, it tells the size of the
+/// index that comes right before it.
+const TEXTURE_CACHE_FOOTER_SIZE: u64 = 0x20;
+
+/// the size of an entry of the index
+const TEXTURE_CACHE_ENTRY_SIZE: u64 = 52;
+
+/// the size of a mip offset of the index
+const TEXTURE_CACHE_MIP_SIZE: u64 = 12;
+
+/// the id at the end of the footer, followed by the version
+const TEXTURE_CACHE_ID: &[u8] = b"HCTX";
+
+/// the version of the texture caches of the game
+const TEXTURE_CACHE_VERSION: u32 = 6;
+
+/// a texture stored in a texture cache
+#[derive(Clone, Debug)]
+pub struct TextureCacheEntry {
+  /// the path of the texture in the game files, with `\` as the separator
+  pub name: String,
+
+  pub width: u16,
+  pub height: u16,
+  pub mip_count: u16,
+}
+
+impl TextureCacheEntry {
+  pub fn dimensions(&self) -> String {
+    format!("{}x{}, {} mips", self.width, self.height, self.mip_count)
+  }
+
+  /// the name used to compare the textures, the game ignores the case and both
+  /// separators are found in the caches.
+  pub fn key(&self) -> String {
+    self.name.replace('/', "\\").to_lowercase()
+  }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+  u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+  let mut buffer = [0; 4];
+  buffer.copy_from_slice(&bytes[offset..offset + 4]);
+
+  u32::from_le_bytes(buffer)
+}
+
+fn invalid_data(message: &str) -> Error {
+  Error::new(ErrorKind::InvalidData, message.to_owned())
+}
+
+/// reads the index at the end of a texture cache. The index is made of the mip
+/// offsets, the names of the textures and their entries, in that order, and
+/// the footer gives the size of each of them and the version of the cache. The
+/// texture data is not read.
+pub fn read_entries(path: &Path) -> std::io::Result<Vec<TextureCacheEntry>> {
+  let mut file = File::open(path)?;
+  let file_size = file.metadata()?.len();
+
+  read_entries_from(&mut file, file_size)
+}
+
+fn read_entries_from(
+  reader: &mut (impl Read + Seek), file_size: u64,
+) -> std::io::Result<Vec<TextureCacheEntry>> {
+  if file_size < TEXTURE_CACHE_FOOTER_SIZE {
+    return Err(invalid_data("the texture cache is too short"));
+  }
+
+  let mut footer = [0; TEXTURE_CACHE_FOOTER_SIZE as usize];
+  reader.seek(SeekFrom::End(-(TEXTURE_CACHE_FOOTER_SIZE as i64)))?;
+  reader.read_exact(&mut footer)?;
+
+  // the sizes are only trusted once the footer is known to be one
+  if &footer[24..28] != TEXTURE_CACHE_ID {
+    return Err(invalid_data("the file is not a texture cache"));
+  }
+
+  if read_u32(&footer, 28) != TEXTURE_CACHE_VERSION {
+    return Err(invalid_data("unsupported texture cache version"));
+  }
+
+  // the footer starts with the crc and the number of used pages
+  let entry_count = read_u32(&footer, 12) as u64;
+  let names_size = read_u32(&footer, 16) as u64;
+  let mip_count = read_u32(&footer, 20) as u64;
+
+  let index_size =
+    mip_count * TEXTURE_CACHE_MIP_SIZE + names_size + entry_count * TEXTURE_CACHE_ENTRY_SIZE;
+
+  if index_size + TEXTURE_CACHE_FOOTER_SIZE > file_size {
+    return Err(invalid_data("the index is larger than the texture cache"));
+  }
+
+  let names_start =
+    file_size - TEXTURE_CACHE_FOOTER_SIZE - index_size + mip_count * TEXTURE_CACHE_MIP_SIZE;
+
+  let mut names = vec![0; names_size as usize];
+  reader.seek(SeekFrom::Start(names_start))?;
+  reader.read_exact(&mut names)?;
+
+  let mut entries = vec![0; (entry_count * TEXTURE_CACHE_ENTRY_SIZE) as usize];
+  reader.read_exact(&mut entries)?;
+
+  // every entry has the offset of its null-terminated name in the names
+  let mut entries = entries
+    .chunks_exact(TEXTURE_CACHE_ENTRY_SIZE as usize)
+    .map(|entry| {
+      let name_start = read_u32(entry, 4) as usize;
+
+      let name = names
+        .get(name_start..)
+        .ok_or_else(|| invalid_data("a texture name is out of the index"))?;
+      let name_end = name
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(name.len());
+
+      Ok(TextureCacheEntry {
+        name: String::from_utf8_lossy(&name[..name_end]).into_owned(),
+        width: read_u16(entry, 24),
+        height: read_u16(entry, 26),
+        mip_count: read_u16(entry, 28),
+      })
+    })
+    .collect::<std::io::Result<Vec<_>>>()?;
+
+  entries.retain(|entry| !entry.name.is_empty());
+
+  Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  /// a texture cache with the textures, the data of the textures and the mip
+  /// offsets are left empty.
+  fn texture_cache(textures: &[(&str, u16, u16)]) -> Vec<u8> {
+    let mut names = Vec::new();
+    let mut entries = Vec::new();
+
+    for (name, width, height) in textures {
+      let mut entry = [0; TEXTURE_CACHE_ENTRY_SIZE as usize];
+      entry[4..8].copy_from_slice(&(names.len() as u32).to_le_bytes());
+      entry[24..26].copy_from_slice(&width.to_le_bytes());
+      entry[26..28].copy_from_slice(&height.to_le_bytes());
+      entry[28..30].copy_from_slice(&3u16.to_le_bytes());
+      entries.extend_from_slice(&entry);
+
+      names.extend_from_slice(name.as_bytes());
+      names.push(0);
+    }
+
+    let mut footer = [0; TEXTURE_CACHE_FOOTER_SIZE as usize];
+    footer[12..16].copy_from_slice(&(textures.len() as u32).to_le_bytes());
+    footer[16..20].copy_from_slice(&(names.len() as u32).to_le_bytes());
+    footer[20..24].copy_from_slice(&(textures.len() as u32).to_le_bytes());
+    footer[24..28].copy_from_slice(TEXTURE_CACHE_ID);
+    footer[28..32].copy_from_slice(&TEXTURE_CACHE_VERSION.to_le_bytes());
+
+    let mut cache = vec![0; 64];
+    cache.extend(vec![0; textures.len() * TEXTURE_CACHE_MIP_SIZE as usize]);
+    cache.extend_from_slice(&names);
+    cache.extend_from_slice(&entries);
+    cache.extend_from_slice(&footer);
+
+    cache
+  }
+
+  fn read(cache: &[u8]) -> std::io::Result<Vec<TextureCacheEntry>> {
+    read_entries_from(&mut Cursor::new(cache), cache.len() as u64)
+  }
+
+  #[test]
+  fn reads_the_names_and_dimensions() {
+    let cache = texture_cache(&[
+      ("environment\\textures\\grass.xbm", 1024, 512),
+      ("characters/models/geralt/hair.xbm", 256, 2048),
+    ]);
+    let entries = read(&cache).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "environment\\textures\\grass.xbm");
+    assert_eq!((entries[0].width, entries[0].height), (1024, 512));
+    assert_eq!(entries[1].name, "characters/models/geralt/hair.xbm");
+    assert_eq!(entries[1].key(), "characters\\models\\geralt\\hair.xbm");
+    assert_eq!(entries[1].dimensions(), "256x2048, 3 mips");
+  }
+
+  #[test]
+  fn empty_cache_has_no_entries() {
+    assert!(read(&texture_cache(&[])).unwrap().is_empty());
+  }
+
+  #[test]
+  fn index_larger_than_the_cache_is_an_error() {
+    let mut cache = texture_cache(&[("a.xbm", 16, 16)]);
+    let footer = cache.len() - TEXTURE_CACHE_FOOTER_SIZE as usize;
+    cache[footer + 12..footer + 16].copy_from_slice(&1000u32.to_le_bytes());
+
+    let error = read(&cache).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("larger"));
+  }
+
+  #[test]
+  fn name_out_of_the_index_is_an_error() {
+    let mut cache = texture_cache(&[("a.xbm", 16, 16)]);
+    let entry =
+      cache.len() - TEXTURE_CACHE_FOOTER_SIZE as usize - TEXTURE_CACHE_ENTRY_SIZE as usize;
+    cache[entry + 4..entry + 8].copy_from_slice(&1000u32.to_le_bytes());
+
+    assert_eq!(read(&cache).unwrap_err().kind(), ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn bad_magic_is_an_error() {
+    let mut cache = texture_cache(&[("a.xbm", 16, 16)]);
+    let footer = cache.len() - TEXTURE_CACHE_FOOTER_SIZE as usize;
+    cache[footer + 24..footer + 28].copy_from_slice(b"XXXX");
+
+    let error = read(&cache).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("not a texture cache"));
+  }
+
+  #[test]
+  fn unknown_version_is_an_error() {
+    let mut cache = texture_cache(&[("a.xbm", 16, 16)]);
+    let footer = cache.len() - TEXTURE_CACHE_FOOTER_SIZE as usize;
+    cache[footer + 28..footer + 32].copy_from_slice(&5u32.to_le_bytes());
+
+    assert_eq!(read(&cache).unwrap_err().kind(), ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn short_cache_is_an_error() {
+    assert_eq!(read(b"HCTX").unwrap_err().kind(), ErrorKind::InvalidData);
+  }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::models::database_index::{open_effective_entries, EffectiveEntries, EntryCategory};
+use crate::models::script_conflicts::is_mergedfiles;
+use crate::models::texture_cache::{read_entries, TextureCacheEntry};
+
+/// where the texture cache is stored in a mod, relative to the mod folder
+const MOD_TEXTURE_CACHE_PATH: &str = "content/texture.cache";
+
+/// a mod whose texture cache contains a contested texture
+#[derive(Clone, Debug)]
+pub struct TextureOverrideMod {
+  pub mod_name: String,
+
+  /// the modlist the mod comes from, it is the analyzed modlist itself when the
+  /// mod is not imported.
+  pub modlist: String,
+
+  pub entry: TextureCacheEntry,
+}
+
+/// a texture that is in the texture cache of more than one mod
+#[derive(Clone, Debug)]
+pub struct TextureOverride {
+  /// the path of the texture, as the first mod writes it
+  pub name: String,
+
+  /// the mods that contain the texture, in the order the game loads them. The
+  /// first one is the one the game uses.
+  pub mods: Vec<TextureOverrideMod>,
+}
+
+impl TextureOverride {
+  /// the mod whose texture the game uses
+  pub fn winner(&self) -> &TextureOverrideMod {
+    &self.mods[0]
+  }
+}
+
+pub struct TextureOverrideReport {
+  pub overrides: Vec<TextureOverride>,
+
+  /// the number of mods with a texture cache in the effective mods set
+  pub texture_mods: usize,
+
+  /// the number of textures the caches of the mods contain, contested or not
+  pub textures: usize,
+
+  /// the texture caches that could not be read, with the reason
+  pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// lists the textures that are in the texture cache of more than one mod of the
+/// modlist, with the mods from the imports included.
+pub fn analyze(modlist_name: &str) -> std::io::Result<TextureOverrideReport> {
+  let EffectiveEntries {
+    index,
+    entries: effective_entries,
+    ..
+  } = open_effective_entries(modlist_name)?;
+
+  // the game loads the mods in alphabetical order, the case is ignored
+  let mut mods = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Mod)
+    .filter(|entry| !entry.name.starts_with('~') && !is_mergedfiles(&entry.name))
+    .collect::<Vec<_>>();
+
+  mods.sort_by_key(|entry| entry.name.to_lowercase());
+
+  // the files of every modlist the mods come from, read once per modlist
+  let mut files_by_modlist = BTreeMap::new();
+  for entry in &mods {
+    if !files_by_modlist.contains_key(entry.modlist.as_str()) {
+      files_by_modlist.insert(entry.modlist.as_str(), index.get_files(&entry.modlist)?);
+    }
+  }
+
+  let mut textures: BTreeMap<String, TextureOverride> = BTreeMap::new();
+  let mut texture_mods = 0;
+  let mut unreadable = Vec::new();
+
+  for entry in &mods {
+    let cache = files_by_modlist
+      .get(entry.modlist.as_str())
+      .map(|files| files.as_slice())
+      .unwrap_or_default()
+      .iter()
+      .find(|file| {
+        file.category == EntryCategory::Mod
+          && file.entry == entry.name
+          && file.path.to_lowercase() == MOD_TEXTURE_CACHE_PATH
+      });
+
+    let cache = match cache {
+      Some(cache) => cache,
+      None => continue,
+    };
+
+    texture_mods += 1;
+
+    let cache_entries = match read_entries(&cache.disk_path()) {
+      Ok(cache_entries) => cache_entries,
+      Err(error) => {
+        unreadable.push((cache.disk_path(), error.to_string()));
+        continue;
+      }
+    };
+
+    for cache_entry in cache_entries {
+      let texture = textures
+        .entry(cache_entry.key())
+        .or_insert_with(|| TextureOverride {
+          name: cache_entry.name.clone(),
+          mods: Vec::new(),
+        });
+
+      texture.mods.push(TextureOverrideMod {
+        mod_name: entry.name.clone(),
+        modlist: entry.modlist.clone(),
+        entry: cache_entry,
+      });
+    }
+  }
+
+  let textures_count = textures.len();
+  let overrides = textures
+    .into_values()
+    .filter(|texture| texture.mods.len() > 1)
+    .collect();
+
+  Ok(TextureOverrideReport {
+    overrides,
+    texture_mods,
+    textures: textures_count,
+    unreadable,
+  })
+}
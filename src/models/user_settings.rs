@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::constants;
+use crate::models::database_index::{open_effective_entries, EntryCategory};
+use crate::models::menu_check::{parse_menu, MenuDisplayType, MenuGroup, MenuRef, MenuVar};
+use crate::models::modlist::ModList;
+use crate::models::script_merge::read_script;
+use crate::models::settings_file::SettingsFile;
+use crate::utils::symlinks::write_replacing_symlink;
+
+/// a menu of the modlist and the groups it defines
+pub struct MenuSettings {
+  pub menu: MenuRef,
+  pub groups: Vec<MenuGroup>,
+}
+
+pub fn user_settings_path(modlist: &ModList) -> PathBuf {
+  modlist.saves_path().join(constants::USER_SETTINGS_NAME)
+}
+
+/// reads the user.settings file of the modlist, an empty one if the game never
+/// wrote it.
+pub fn read_user_settings(modlist: &ModList) -> std::io::Result<SettingsFile> {
+  let path = user_settings_path(modlist);
+
+  if !path.exists() {
+    return Ok(SettingsFile::parse(""));
+  }
+
+  Ok(SettingsFile::parse(&read_script(&path)?))
+}
+
+pub fn write_user_settings(modlist: &ModList, settings: &SettingsFile) -> std::io::Result<()> {
+  write_replacing_symlink(&user_settings_path(modlist), settings.to_text())
+}
+
+/// parses the menus of the mods of the modlist, imported menus included. The
+/// menus of the game and the malformed ones are left out.
+pub fn get_mod_menus(modlist_name: &str) -> std::io::Result<Vec<MenuSettings>> {
+  let effective_entries = open_effective_entries(modlist_name)?.entries;
+
+  let mut menus = effective_entries
+    .iter()
+    .filter(|entry| entry.category == EntryCategory::Menu)
+    .filter(|entry| entry.name.ends_with(".xml") && entry.modlist != "vanilla")
+    .collect::<Vec<_>>();
+
+  menus.sort_by_key(|entry| entry.name.to_lowercase());
+
+  let mut output = Vec::new();
+
+  for entry in menus {
+    let path = ModList::get_by_name(&entry.modlist)
+      .map(|modlist| modlist.menus_path().join(&entry.name))
+      .unwrap_or_default();
+
+    if let Ok(groups) = parse_menu(&path) {
+      output.push(MenuSettings {
+        menu: MenuRef {
+          name: entry.name.clone(),
+          modlist: entry.modlist.clone(),
+        },
+        groups,
+      });
+    }
+  }
+
+  Ok(output)
+}
+
+/// whether the value can be written for the variable, it must fit its display
+/// type. A line break or a bracket would start a new line or a new group in the
+/// user.settings file.
+pub fn validate_value(var: &MenuVar, value: &str) -> Result<(), String> {
+  if value.contains(['\n', '\r', '[', ']']) {
+    return Err(format!(
+      "the value of {} has a line break or a bracket",
+      var.id
+    ));
+  }
+
+  match &var.display_type {
+    MenuDisplayType::Slider { min, max, .. } => {
+      let number = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("the value of {} is not a number", var.id))?;
+
+      if !(min.min(*max)..=max.max(*min)).contains(&number) {
+        return Err(format!(
+          "the value of {} is not between {} and {}",
+          var.id, min, max
+        ));
+      }
+    }
+    MenuDisplayType::Toggle => {
+      if value != "true" && value != "false" {
+        return Err(format!("the value of {} is not true or false", var.id));
+      }
+    }
+    MenuDisplayType::Options(options) => {
+      if !options.iter().any(|(id, _)| id == value) {
+        return Err(format!("the value of {} is not one of its options", var.id));
+      }
+    }
+    MenuDisplayType::Separator => {
+      return Err(format!("{} is a separator, it has no value", var.id));
+    }
+    MenuDisplayType::Other(_) => {}
+  }
+
+  Ok(())
+}
+
+/// checks that every value is for a variable of the groups of the menus, and
+/// that it fits the variable. Returns the first error.
+pub fn validate_user_settings(
+  menus: &[MenuSettings], values: &HashMap<(String, String), String>,
+) -> Result<(), String> {
+  for ((group, var), value) in values {
+    let menu_var = menus
+      .iter()
+      .flat_map(|menu| menu.groups.iter())
+      .filter(|menu_group| &menu_group.id == group)
+      .flat_map(|menu_group| menu_group.vars.iter())
+      .find(|menu_var| &menu_var.id == var)
+      .ok_or_else(|| format!("no menu has the variable {} in the group {}", var, group))?;
+
+    validate_value(menu_var, value)?;
+  }
+
+  Ok(())
+}
+
+/// writes the values of the variables to the user.settings file of the
+/// modlist. `values` is indexed by group then variable id, the variables
+/// whose value didn't change are not in it. Returns the number of values
+/// written.
+pub fn update_user_settings(
+  modlist: &ModList, values: &HashMap<(String, String), String>,
+) -> std::io::Result<usize> {
+  if values.is_empty() {
+    return Ok(0);
+  }
+
+  let mut settings = read_user_settings(modlist)?;
+
+  for ((group, var), value) in values {
+    settings.set(group, var, value);
+  }
+
+  write_user_settings(modlist, &settings)?;
+
+  Ok(values.len())
+}
+
+/// removes the values of the group from the user.settings file of the modlist,
+/// the game uses the default values of the menu the next time it starts.
+pub fn reset_group(modlist: &ModList, group: &str) -> std::io::Result<bool> {
+  let mut settings = read_user_settings(modlist)?;
+
+  if !settings.remove_context(group) {
+    return Ok(false);
+  }
+
+  write_user_settings(modlist, &settings)?;
+
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn var(id: &str, display_type: MenuDisplayType) -> MenuVar {
+    MenuVar {
+      id: id.to_owned(),
+      display_name: id.to_owned(),
+      display_type,
+    }
+  }
+
+  fn menus() -> Vec<MenuSettings> {
+    vec![MenuSettings {
+      menu: MenuRef {
+        name: String::from("modFriendlyHUD.xml"),
+        modlist: String::from("hud"),
+      },
+      groups: vec![MenuGroup {
+        id: String::from("fhudConfig"),
+        display_name: String::from("Friendly HUD"),
+        vars: vec![
+          var(
+            "fhudScale",
+            MenuDisplayType::Slider {
+              min: 0.5,
+              max: 2.0,
+              steps: 15,
+            },
+          ),
+          var("fhudEnabled", MenuDisplayType::Toggle),
+        ],
+      }],
+    }]
+  }
+
+  fn values(group: &str, var: &str, value: &str) -> HashMap<(String, String), String> {
+    let mut values = HashMap::new();
+    values.insert((group.to_owned(), var.to_owned()), value.to_owned());
+
+    values
+  }
+
+  #[test]
+  fn values_that_fit_are_valid() {
+    let slider = var(
+      "scale",
+      MenuDisplayType::Slider {
+        min: 0.0,
+        max: 10.0,
+        steps: 10,
+      },
+    );
+    let options = var(
+      "mode",
+      MenuDisplayType::Options(vec![(String::from("0"), String::from("off"))]),
+    );
+
+    assert!(validate_value(&slider, "2.5").is_ok());
+    assert!(validate_value(&slider, "10").is_ok());
+    assert!(validate_value(&var("on", MenuDisplayType::Toggle), "true").is_ok());
+    assert!(validate_value(&options, "0").is_ok());
+    assert!(validate_value(
+      &var("text", MenuDisplayType::Other(String::from("INPUT"))),
+      "any"
+    )
+    .is_ok());
+  }
+
+  #[test]
+  fn values_that_dont_fit_are_errors() {
+    let slider = var(
+      "scale",
+      MenuDisplayType::Slider {
+        min: 0.0,
+        max: 10.0,
+        steps: 10,
+      },
+    );
+    let options = var(
+      "mode",
+      MenuDisplayType::Options(vec![(String::from("0"), String::from("off"))]),
+    );
+
+    assert!(validate_value(&slider, "11").is_err());
+    assert!(validate_value(&slider, "NaN").is_err());
+    assert!(validate_value(&slider, "abc").is_err());
+    assert!(validate_value(&var("on", MenuDisplayType::Toggle), "1").is_err());
+    assert!(validate_value(&options, "1").is_err());
+    assert!(validate_value(&var("sep", MenuDisplayType::Separator), "").is_err());
+  }
+
+  #[test]
+  fn line_breaks_and_brackets_are_errors() {
+    let text = var("text", MenuDisplayType::Other(String::from("INPUT")));
+
+    assert!(validate_value(&text, "a\n[otherGroup]").is_err());
+    assert!(validate_value(&text, "a\r").is_err());
+    assert!(validate_value(&text, "[a").is_err());
+  }
+
+  #[test]
+  fn unknown_groups_and_variables_are_errors() {
+    let menus = menus();
+
+    assert!(validate_user_settings(&menus, &values("fhudConfig", "fhudScale", "1.5")).is_ok());
+    assert!(validate_user_settings(&menus, &values("fhudConfig", "fhudScale", "3")).is_err());
+    assert!(validate_user_settings(&menus, &values("fhudConfig", "unknown", "1")).is_err());
+    assert!(validate_user_settings(&menus, &values("unknown", "fhudEnabled", "true")).is_err());
+  }
+}
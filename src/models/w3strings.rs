@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// the magic every w3strings file starts with
+const W3STRINGS_MAGIC: &[u8; 4] = b"RTSW";
+
+/// the size of an entry of the string index: the id, the offset of the text
+/// and its length.
+const W3STRINGS_INDEX_ENTRY_SIZE: usize = 12;
+
+/// the size of an entry of the key table: the hash of the key of the string
+/// and its id.
+const W3STRINGS_KEY_ENTRY_SIZE: usize = 8;
+
+/// a localized string of a w3strings file, its text is not decoded
+#[derive(Clone, Debug)]
+pub struct W3StringsEntry {
+  pub id: u32,
+
+  /// the hash of the key the scripts use to find the string, `None` for the
+  /// strings that are only found by their id.
+  pub key_hash: Option<u32>,
+}
+
+/// the strings of a w3strings file for a single language
+#[derive(Clone, Debug)]
+pub struct W3StringsFile {
+  /// the language, from the name of the file like `en` for `en.w3strings`
+  pub language: String,
+
+  pub entries: Vec<W3StringsEntry>,
+}
+
+fn invalid_data(message: &str) -> Error {
+  Error::new(ErrorKind::InvalidData, message.to_owned())
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, size: usize) -> std::io::Result<&'a [u8]> {
+    if self.offset + size > self.bytes.len() {
+      return Err(invalid_data("the w3strings file is truncated"));
+    }
+
+    let bytes = &self.bytes[self.offset..self.offset + size];
+    self.offset += size;
+
+    Ok(bytes)
+  }
+
+  fn u16(&mut self) -> std::io::Result<u16> {
+    let bytes = self.take(2)?;
+
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+  }
+
+  fn u32(&mut self) -> std::io::Result<u32> {
+    let bytes = self.take(4)?;
+
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+  }
+
+  /// reads a count with the variable length encoding of the game. The first
+  /// byte holds 6 bits, the sign and whether more bytes follow, the next ones
+  /// 7 bits and whether more bytes follow.
+  fn vlq(&mut self) -> std::io::Result<usize> {
+    let first = self.take(1)?[0];
+    let mut value = (first & 0x3F) as usize;
+    let mut has_more = first & 0x40 != 0;
+    let mut shift = 6;
+
+    while has_more {
+      if shift > 27 {
+        return Err(invalid_data("a count of the w3strings file is too large"));
+      }
+
+      let byte = self.take(1)?[0];
+      value |= ((byte & 0x7F) as usize) << shift;
+      has_more = byte & 0x80 != 0;
+      shift += 7;
+    }
+
+    Ok(value)
+  }
+}
+
+/// reads the ids of the strings of a w3strings file.
+///
+/// The file is made of the header, the string index, the key table, the
+/// encrypted texts in UTF-16 and the footer. The key of the file is split in
+/// two: its high half is right after the header and its low half is the
+/// footer. The ids of the index and of the key table are stored xor-ed with
+/// that key.
+pub fn read_w3strings(path: &Path) -> std::io::Result<W3StringsFile> {
+  let bytes = fs::read(path)?;
+  let language = path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+
+  parse_w3strings(&bytes, language)
+}
+
+fn parse_w3strings(bytes: &[u8], language: String) -> std::io::Result<W3StringsFile> {
+  let mut reader = Reader { bytes, offset: 0 };
+
+  if reader.take(W3STRINGS_MAGIC.len())? != W3STRINGS_MAGIC {
+    return Err(invalid_data(
+      "not a w3strings file, the header doesn't match",
+    ));
+  }
+
+  let _version = reader.u32()?;
+  let key_high = reader.u16()?;
+
+  if bytes.len() < reader.offset + 2 {
+    return Err(invalid_data("the w3strings file is truncated"));
+  }
+
+  let key_low = u16::from_le_bytes([bytes[bytes.len() - 2], bytes[bytes.len() - 1]]);
+  let key = (key_high as u32) << 16 | key_low as u32;
+
+  let index_count = reader.vlq()?;
+  let index = reader.take(index_count * W3STRINGS_INDEX_ENTRY_SIZE)?;
+
+  let keys_count = reader.vlq()?;
+  let keys = reader.take(keys_count * W3STRINGS_KEY_ENTRY_SIZE)?;
+
+  let key_hashes = keys
+    .chunks_exact(W3STRINGS_KEY_ENTRY_SIZE)
+    .map(|entry| {
+      let mut entry = Reader {
+        bytes: entry,
+        offset: 0,
+      };
+
+      let hash = entry.u32()?;
+      let id = entry.u32()? ^ key;
+
+      Ok((id, hash))
+    })
+    .collect::<std::io::Result<HashMap<u32, u32>>>()?;
+
+  let entries = index
+    .chunks_exact(W3STRINGS_INDEX_ENTRY_SIZE)
+    .map(|entry| {
+      let mut entry = Reader {
+        bytes: entry,
+        offset: 0,
+      };
+      let id = entry.u32()? ^ key;
+
+      Ok(W3StringsEntry {
+        id,
+        key_hash: key_hashes.get(&id).cloned(),
+      })
+    })
+    .collect::<std::io::Result<Vec<_>>>()?;
+
+  Ok(W3StringsFile { language, entries })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const KEY: u32 = 0x43876CA2;
+
+  /// a w3strings file with the strings, given as their id and the hash of
+  /// their key. The texts are left empty.
+  fn w3strings(strings: &[(u32, Option<u32>)]) -> Vec<u8> {
+    let mut bytes = W3STRINGS_MAGIC.to_vec();
+    bytes.extend_from_slice(&162u32.to_le_bytes());
+    bytes.extend_from_slice(&((KEY >> 16) as u16).to_le_bytes());
+
+    // the counts are below 64, they fit in a single byte
+    bytes.push(strings.len() as u8);
+    for (id, _) in strings {
+      bytes.extend_from_slice(&(id ^ KEY).to_le_bytes());
+      bytes.extend_from_slice(&[0; 8]);
+    }
+
+    let keys = strings
+      .iter()
+      .filter_map(|(id, hash)| hash.map(|hash| (*id, hash)))
+      .collect::<Vec<_>>();
+
+    bytes.push(keys.len() as u8);
+    for (id, hash) in keys {
+      bytes.extend_from_slice(&hash.to_le_bytes());
+      bytes.extend_from_slice(&(id ^ KEY).to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&(KEY as u16).to_le_bytes());
+
+    bytes
+  }
+
+  #[test]
+  fn decodes_the_ids_and_the_key_hashes() {
+    let bytes = w3strings(&[
+      (2115940101, Some(0xDEADBEEF)),
+      (2115940102, None),
+      (1, Some(7)),
+    ]);
+    let file = parse_w3strings(&bytes, String::from("en")).unwrap();
+
+    assert_eq!(file.language, "en");
+    assert_eq!(
+      file
+        .entries
+        .iter()
+        .map(|entry| (entry.id, entry.key_hash))
+        .collect::<Vec<_>>(),
+      vec![
+        (2115940101, Some(0xDEADBEEF)),
+        (2115940102, None),
+        (1, Some(7))
+      ]
+    );
+  }
+
+  #[test]
+  fn multi_byte_counts_are_decoded() {
+    let mut reader = Reader {
+      bytes: &[0x41, 0x02],
+      offset: 0,
+    };
+
+    assert_eq!(reader.vlq().unwrap(), 1 | 2 << 6);
+  }
+
+  #[test]
+  fn truncated_file_is_an_error() {
+    let bytes = w3strings(&[(1, Some(7)), (2, None)]);
+
+    // the footer is read from the end of the file, a file cut in the footer
+    // still has all of its tables
+    for size in 0..bytes.len() - 2 {
+      let error = parse_w3strings(&bytes[..size], String::from("en")).unwrap_err();
+
+      assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+  }
+
+  #[test]
+  fn bad_magic_is_an_error() {
+    let mut bytes = w3strings(&[(1, None)]);
+    bytes[0] = b'X';
+
+    assert!(parse_w3strings(&bytes, String::from("en")).is_err());
+  }
+}
@@ -0,0 +1,121 @@
+use crate::components;
+use crate::models::database_config::DatabaseConfig;
+use crate::models::modlist::ModList;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(_req: HttpRequest) -> HttpResponse {
+  let config = match DatabaseConfig::read_from_disk() {
+    Ok(config) => config,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read the database config" }
+        p { (error) }
+      };
+      let view = components::page("groups", &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let modlists = ModList::get_all()
+    .iter()
+    .map(ModList::read_metadata_from_disk_copy)
+    .filter_map(Result::ok)
+    .collect::<Vec<ModList>>();
+
+  let content = html! {
+    h1 { "groups" }
+
+    section.groups {
+      ul {
+        @for group in &config.groups {
+          @let count = modlists
+            .iter()
+            .filter(|modlist| modlist.group.as_deref() == Some(group.name.as_str()))
+            .count();
+
+          li.group {
+            div.row.baseline {
+              a href={"/?group=" (group.name)} { (group.name) }
+              span.small { " " (count) " modlists" }
+
+              span.row.actions {
+                form method="post" action="/api/group/move-up" {
+                  input type="hidden" name="group_name" value=(group.name);
+                  input type="submit" class="rotate-90-clockwise text-style" value="<";
+                }
+
+                form method="post" action="/api/group/move-down" {
+                  input type="hidden" name="group_name" value=(group.name);
+                  input type="submit" class="rotate-90-clockwise text-style" value=">";
+                }
+
+                form method="post" action="/api/group/delete" {
+                  input type="hidden" name="group_name" value=(group.name);
+                  input type="submit" value="delete" title="the modlists in the group won't be deleted, they will be ungrouped";
+                }
+              }
+            }
+
+            form.update method="post" action="/api/group/update" {
+              input type="hidden" name="group_name" value=(group.name);
+              input type="text" name="new_group_name" value=(group.name);
+              input type="text" name="description" value=(group.description) placeholder="description";
+              input type="submit" value="update";
+            }
+          }
+        }
+      }
+
+      form.create method="post" action="/api/group/create" {
+        h2 { "New group" }
+        input type="text" name="group_name" placeholder="group's name";
+        input type="text" name="description" placeholder="description";
+        input type="submit" value="new";
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page("groups", &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    section.groups {
+      margin: auto;
+      max-width: 600px;
+    }
+
+    section.groups ul {
+      list-style: none;
+      padding-left: 0;
+    }
+
+    section.groups .group {
+      margin-bottom: 1em;
+      padding-left: 1em;
+      border-left: solid 1px white;
+    }
+
+    section.groups .group .actions {
+      margin-left: auto;
+    }
+
+    section.groups form.update input + input,
+    section.groups form.create input + input {
+      margin-left: 8px;
+    }
+  "
+  .to_owned()
+}
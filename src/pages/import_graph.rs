@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::components;
+use crate::models::import_graph::ImportGraph;
+use crate::utils::format_size;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+const NODE_WIDTH: usize = 200;
+const NODE_HEIGHT: usize = 36;
+const COLUMN_GAP: usize = 120;
+const ROW_GAP: usize = 20;
+
+pub async fn render(_req: HttpRequest) -> HttpResponse {
+  let graph = match ImportGraph::from_database() {
+    Ok(graph) => graph,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read modlist metadata" }
+        p { (error) }
+      };
+      let view = components::page("import graph", &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let orphans = graph.get_orphans();
+  let missing_vanilla = graph.get_missing_vanilla();
+  let invalid_edges = graph.get_invalid_edges();
+  let cycles = graph.get_cycles();
+
+  let flagged_names: HashSet<&str> = orphans
+    .iter()
+    .chain(missing_vanilla.iter())
+    .map(|node| node.name.as_str())
+    .chain(cycles.iter().flatten().map(|name| name.as_str()))
+    .collect();
+
+  // the modlists are placed in columns based on their level, the modlists with
+  // the longest import chains on the left and vanilla on the right.
+  let levels = graph.get_levels();
+  let max_level = levels.values().cloned().max().unwrap_or(0);
+
+  let mut positions: HashMap<&str, (usize, usize)> = HashMap::new();
+  let mut rows_per_column = vec![0; max_level + 1];
+
+  for node in &graph.nodes {
+    let level = levels.get(node.name.as_str()).cloned().unwrap_or(0);
+    let column = max_level - level;
+    let row = rows_per_column[column];
+    rows_per_column[column] += 1;
+
+    let x = column * (NODE_WIDTH + COLUMN_GAP);
+    let y = row * (NODE_HEIGHT + ROW_GAP);
+    positions.insert(&node.name, (x, y));
+  }
+
+  let max_rows = rows_per_column.iter().cloned().max().unwrap_or(0);
+  let width = (max_level + 1) * (NODE_WIDTH + COLUMN_GAP);
+  let height = max_rows * (NODE_HEIGHT + ROW_GAP);
+
+  let edge_paths: Vec<(String, bool)> = graph
+    .edges
+    .iter()
+    .filter_map(|edge| {
+      let (from_x, from_y) = positions.get(edge.from.as_str())?;
+      let (to_x, to_y) = positions.get(edge.to.as_str())?;
+
+      let start_x = from_x + NODE_WIDTH;
+      let start_y = from_y + NODE_HEIGHT / 2;
+      let end_x = *to_x;
+      let end_y = to_y + NODE_HEIGHT / 2;
+      let middle_x = (start_x + end_x) / 2;
+
+      let path = format!(
+        "M {} {} C {} {}, {} {}, {} {}",
+        start_x, start_y, middle_x, start_y, middle_x, end_y, end_x, end_y
+      );
+
+      // imports going right to left can only happen with cyclic imports
+      let is_backward = end_x <= start_x;
+
+      Some((path, is_backward))
+    })
+    .collect();
+
+  let edge_labels: Vec<(usize, usize, String)> = graph
+    .edges
+    .iter()
+    .filter_map(|edge| {
+      let (from_x, from_y) = positions.get(edge.from.as_str())?;
+      let (_, to_y) = positions.get(edge.to.as_str())?;
+
+      // the import order is displayed next to the importing modlist, slightly
+      // moved toward the target so labels of a same modlist don't overlap
+      let x = from_x + NODE_WIDTH + 8;
+      let y = (from_y * 3 + to_y) / 4 + NODE_HEIGHT / 2 - 2;
+
+      Some((x, y, format!("{}", edge.order + 1)))
+    })
+    .collect();
+
+  let content = html! {
+    h1 { "import graph" }
+
+    div.row.center.exports {
+      a href="/api/graph/imports.json" { "json" }
+      a href="/api/graph/imports.dot" { "dot" }
+    }
+
+    div.graph {
+      svg xmlns="http://www.w3.org/2000/svg" width=(width) height=(height) viewBox={"0 0 " (width) " " (height)} {
+        @for (path, is_backward) in &edge_paths {
+          @if *is_backward {
+            path.edge.backward d=(path) {}
+          } @else {
+            path.edge d=(path) {}
+          }
+        }
+
+        @for (x, y, label) in &edge_labels {
+          text.edge-label x=(x) y=(y) { (label) }
+        }
+
+        @for node in &graph.nodes {
+          @if let Some(&(x, y)) = positions.get(node.name.as_str()) {
+            @let text_x = x + 8;
+            @let name_y = y + 15;
+            @let size_y = y + 29;
+
+            a href={"/modlist/" (node.name)} {
+              g class={
+                "node"
+                (if node.installable { " installable" } else { "" })
+                (if flagged_names.contains(node.name.as_str()) { " flagged" } else { "" })
+              } {
+                title {
+                  "group: " (node.group.as_deref().unwrap_or("ungrouped")) ", size: " (format_size(node.size))
+                }
+
+                rect x=(x) y=(y) width=(NODE_WIDTH) height=(NODE_HEIGHT) rx="3" {}
+                text x=(text_x) y=(name_y) { (node.name) }
+                text.size x=(text_x) y=(size_y) { (format_size(node.size)) }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    div.row.reports {
+      div.column {
+        h2 title="modlists that import nothing and that no modlist imports" { "Orphans" }
+        ul {
+          @for node in &orphans {
+            li { (components::modlist_link(&node.name)) }
+          }
+          @if orphans.is_empty() {
+            li.small { "none" }
+          }
+        }
+      }
+
+      div.column {
+        h2 title="modlists that import other modlists but not vanilla, and that no modlist imports" { "Missing vanilla" }
+        ul {
+          @for node in &missing_vanilla {
+            li { (components::modlist_link(&node.name)) }
+          }
+          @if missing_vanilla.is_empty() {
+            li.small { "none" }
+          }
+        }
+      }
+
+      div.column {
+        h2 title="modlists that import each other in a loop" { "Cycles" }
+        ul {
+          @for cycle in &cycles {
+            li {
+              @for name in cycle {
+                (components::modlist_link(name)) " → "
+              }
+              (components::modlist_link(&cycle[0]))
+            }
+          }
+          @if cycles.is_empty() {
+            li.small { "none" }
+          }
+        }
+      }
+
+      div.column {
+        h2 title="imports of modlists that do not exist" { "Missing imports" }
+        ul {
+          @for edge in &invalid_edges {
+            li { (components::modlist_link(&edge.from)) " → " (edge.to) }
+          }
+          @if invalid_edges.is_empty() {
+            li.small { "none" }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page("import graph", &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    .exports a + a {
+      margin-left: 1em;
+    }
+
+    .graph {
+      overflow: auto;
+      padding: 2em;
+    }
+
+    .graph svg {
+      overflow: visible;
+      font-size: 13px;
+    }
+
+    .graph .edge {
+      fill: none;
+      stroke: rgba(250, 250, 250, 0.3);
+      stroke-width: 1.5px;
+    }
+
+    .graph .edge.backward {
+      stroke: #E91E63;
+      stroke-dasharray: 4 2;
+    }
+
+    .graph .edge-label {
+      fill: grey;
+      font-size: 10px;
+    }
+
+    .graph .node rect {
+      fill: #2a2731;
+      stroke: rgba(250, 250, 250, 0.3);
+    }
+
+    .graph .node.installable rect {
+      stroke: var(--var-color-accent);
+    }
+
+    .graph .node.flagged rect {
+      stroke: #E91E63;
+    }
+
+    .graph .node text {
+      fill: white;
+    }
+
+    .graph .node text.size {
+      fill: grey;
+      font-size: 10px;
+    }
+
+    .graph .node:hover rect {
+      fill: #3a3741;
+    }
+
+    .reports {
+      justify-content: space-evenly;
+    }
+
+    .reports h2 {
+      cursor: help;
+    }
+
+    .reports ul {
+      padding-left: 0;
+      list-style: none;
+    }
+  "
+  .to_owned()
+}
@@ -1,5 +1,18 @@
+pub mod groups;
+pub mod import_graph;
 pub mod modlist;
+pub mod modlist_bundle_conflicts;
+pub mod modlist_conflicts;
 pub mod modlist_edit;
 pub mod modlist_folder_edit;
+pub mod modlist_menus;
 pub mod modlist_merge;
+pub mod modlist_merge_status;
+pub mod modlist_mods_settings;
+pub mod modlist_resolutions;
+pub mod modlist_strings;
+pub mod modlist_textures;
+pub mod modlist_user_settings;
 pub mod root;
+pub mod script_diff;
+pub mod search;
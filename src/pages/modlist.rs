@@ -1,7 +1,13 @@
 use std::path::PathBuf;
 
 use crate::components;
+use crate::models::database_config::DatabaseConfig;
+use crate::models::menu_check;
+use crate::models::merge_inventory::{self, InventoryMergeKind, MergeInventory};
+use crate::models::merge_manifest::{MergeManifest, MergeStatus};
 use crate::models::modlist::ModList;
+use crate::models::script_merge;
+use crate::models::texture_conflicts;
 use crate::utils::symlinks::get_children_without_symlinks;
 
 use actix_web::HttpRequest;
@@ -51,6 +57,16 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     .count()
     > 0;
 
+  let dependents = modlist.get_dependents();
+
+  let groups = DatabaseConfig::read_from_disk()
+    .map(|config| config.groups)
+    .unwrap_or_default();
+
+  let merge_manifest = MergeManifest::read_from_disk(&modlist).ok().flatten();
+  let texture_report = texture_conflicts::analyze(&modlist.name).ok();
+  let inventory = merge_inventory::analyze(&modlist.name);
+
   let packing_help = "
     Packing transforms a modlist in a way that allows you to pre-merge the mods
     and then re-use the merged mods directly the next time you import the modlist.
@@ -97,14 +113,19 @@ should organize your modlists in a way where you compose lots of small packed
 modlists to build larger modlists. But the larger modlist should NEVER be packed.
   ";
 
-  let visibility_level_help = "
-This number is the `visibility level` of the modlist. You will find the same number
-in the home page, which you can use to filter the modlists per visibility level.
-The value can either be positive or negative, and it's up to you to create a hierarchy
-you understand. For example, you could set modlists you install at level 1 and modlists
-that are imported by other modlists at level -1. This way, when you're on the home page
-you can go left and see the imported modlists or go right and see the modlists you
-can install.
+  let group_help = "
+The group the modlist is displayed in on the home page. Groups are named and
+ordered in the groups page, for example you could have a group for the modlists
+you install and another one for the modlists that are imported by other modlists.
+  ";
+
+  let dependents_help = "
+the modlists listed here import this modlist, either directly or through one of
+their own imports. The number is the position of the import in their load order
+and the state tells whether their imports are currently loaded.
+
+Reloading the imports of the dependents is needed after a change to this modlist,
+so the symlinks they contain point to the new files.
   ";
 
   let merge_help = "
@@ -119,6 +140,68 @@ In this case, import vanilla, load the imports then merge. And after you've merg
 unload the import and remove vanilla and you can safely pack your modlist.
   ";
 
+  let restore_merge_help = "
+puts back the merged scripts from before the last merge. The current merged
+scripts are kept instead, so restoring again undoes it.
+  ";
+
+  let resolutions_help = "
+the resolutions of the conflicts from the previous merges, the next merges
+resolve the same conflicts the same way.
+  ";
+
+  let merge_status_help = "
+whether the merged scripts still match the files they were merged from, and the
+merges of the scriptmerger whose mods are not in the modlist anymore.
+  ";
+
+  let inventory_help = "
+the merges the scriptmerger keeps in the MergeInventory.xml file of the modlist,
+a merge is stale when one of its mods is not in the modlist anymore.
+  ";
+
+  let textures_help = "
+lists the textures that are in the texture cache of more than one mod, the game
+uses the texture of the first mod in the load order.
+  ";
+
+  let strings_help = "
+lists the string ids of the w3strings files that more than one mod uses for the
+same language, the game shows the wrong text for one of them.
+  ";
+
+  let menus_help = "
+checks the menu files of the modlist: the malformed ones, the ids of groups and
+variables more than one menu defines, and the menus no mod seems to use.
+  ";
+
+  let merge_input_help = "
+inserts the key bindings of the input.settings file at the root of every mod
+folder in the input.settings file of the saves of the modlist, in the same
+contexts. The bindings of the mods that were removed since are taken out.
+  ";
+
+  let settings_help = "
+edits the values of the options of the mod menus in the user.settings file of
+the modlist, without launching the game.
+  ";
+
+  let mods_settings_help = "
+the priority and enabled state of every mod in the mods.settings file of the
+modlist, the file the recent versions of the game read. They follow the load
+order unless they are edited.
+  ";
+
+  let conflicts_help = "
+lists the scripts that are in more than one mod of the modlist, including the
+imported mods, to know whether the modlist needs to be merged.
+  ";
+
+  let bundle_conflicts_help = "
+lists the files that are in the bundles of more than one mod of the modlist,
+only the first mod is used unless the bundles are merged.
+  ";
+
   let content = html! {
     section {
       div.row.center.baseline {
@@ -141,6 +224,38 @@ unload the import and remove vanilla and you can safely pack your modlist.
           // }
         }
 
+        @if !modlist.is_packed() {
+          form method="post" action="/api/modlist/merge-input" {
+            input type="hidden" name="modlist_name" value=(modlist.name);
+            input type="submit" value="merge input" class="text-style" title=(merge_input_help);
+          }
+        }
+
+        a.small href={"/modlist/"(modlist.name)"/conflicts"} title=(conflicts_help) { "conflicts" }
+        a.small href={"/modlist/"(modlist.name)"/textures"} title=(textures_help) {
+          "textures"
+
+          @if let Some(report) = &texture_report {
+            @if !report.overrides.is_empty() {
+              span.status.stale { " (" (report.overrides.len()) " overridden)" }
+            }
+          }
+        }
+        a.small href={"/modlist/"(modlist.name)"/bundle-conflicts"} title=(bundle_conflicts_help) { "bundle conflicts" }
+        a.small href={"/modlist/"(modlist.name)"/merge-status"} title=(merge_status_help) { "merge status" }
+        a.small href={"/modlist/"(modlist.name)"/strings"} title=(strings_help) { "string ids" }
+        a.small href={"/modlist/"(modlist.name)"/menus"} title=(menus_help) { "menus" }
+        a.small href={"/modlist/"(modlist.name)"/settings"} title=(settings_help) { "mod settings" }
+        a.small href={"/modlist/"(modlist.name)"/mods-settings"} title=(mods_settings_help) { "mod priorities" }
+        a.small href={"/modlist/"(modlist.name)"/resolutions"} title=(resolutions_help) { "resolutions" }
+
+        @if !modlist.is_packed() && script_merge::has_merged_scripts_backup(&modlist) {
+          form method="post" action="/api/modlist/restore-merged-scripts" {
+            input type="hidden" name="modlist_name" value=(modlist.name);
+            input type="submit" value="restore previous merge" class="text-style" title=(restore_merge_help);
+          }
+        }
+
         @if modlist.is_packed() {
           form method="post" action="/api/modlist/unpack" {
             input type="hidden" name="modlist_name" value=(modlist.name);
@@ -160,21 +275,40 @@ unload the import and remove vanilla and you can safely pack your modlist.
         }
       }
 
-      div class="row flex-center" {
-        form method="post" action="/api/modlist/visibility-down" {
-          input type="hidden" name="modlist_name" value=(modlist.name);
-          input type="hidden" name="imported_modlist_name" value="-1";
+      @if let Some(manifest) = &merge_manifest {
+        (get_merge_status_view(&modlist.name, manifest, merge_status_help))
+      }
 
-          input type="submit" class="text-style" value="<";
+      @match &inventory {
+        Ok(inventory) => {
+          @if !inventory.merges.is_empty() {
+            (get_merge_inventory_view(&modlist.name, inventory, inventory_help))
+          }
         }
+        Err(error) => {
+          div.small.center.summary.status.orphaned {
+            "the merge inventory could not be read: " (error)
+          }
+        }
+      }
 
-        span title=(visibility_level_help) {(modlist.visibility)};
-
-        form method="post" action="/api/modlist/visibility-up" {
+      div class="row flex-center" {
+        form.group method="post" action="/api/modlist/set-group" title=(group_help) {
           input type="hidden" name="modlist_name" value=(modlist.name);
-          input type="hidden" name="imported_modlist_name" value="1";
 
-          input type="submit" class="text-style" value=">";
+          select name="group_name" {
+            option value="" { "ungrouped" }
+
+            @for group in &groups {
+              @if modlist.group.as_deref() == Some(group.name.as_str()) {
+                option value=(group.name) selected { (group.name) }
+              } @else {
+                option value=(group.name) { (group.name) }
+              }
+            }
+          }
+
+          input type="submit" class="text-style" value="change group";
         }
       }
 
@@ -275,6 +409,52 @@ unload the import and remove vanilla and you can safely pack your modlist.
         }
       }
 
+      section class="imports dependents" {
+        h2 title=(dependents_help) { "imported by" }
+
+        @match &dependents {
+          Ok(dependents) => {
+            @if dependents.is_empty() {
+              p.small { "no modlist imports " (modlist.name) }
+            } @else {
+              form method="post" action="/api/modlist/reload-dependents-imports" {
+                input type="hidden" name="modlist_name" value=(modlist.name);
+                input type="submit" value="reload imports in all dependents";
+              }
+
+              ul {
+                @for dependent in dependents {
+                  @let position = dependent.import_position + 1;
+
+                  li {
+                    span {
+                      (components::modlist_link(&dependent.modlist.name))
+
+                      @if dependent.depth > 1 {
+                        span.small { " through " (dependent.imported_through) }
+                      }
+                    }
+
+                    span.row {
+                      span.small title="position in the imports" { "#" (position) }
+
+                      @if dependent.imports_loaded {
+                        span.small.accent { "loaded" }
+                      } @else {
+                        span.small.disabled-folder { "unloaded" }
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          },
+          Err(error) => {
+            p { "Could not read the modlists importing " (modlist.name) ". ERROR: " (error) }
+          }
+        }
+      }
+
       style type="text/css" { (get_stylesheet()) }
       script type="text/javascript" { (maud::PreEscaped(get_javascript())) }
     }
@@ -287,8 +467,63 @@ unload the import and remove vanilla and you can safely pack your modlist.
     .body(view.into_string())
 }
 
+fn get_merge_status_view(modlist_name: &str, manifest: &MergeManifest, help: &str) -> maud::Markup {
+  let (status, scripts) = manifest.status();
+
+  html! {
+    div.small.center.summary title=(help) {
+      a href={"/modlist/"(modlist_name)"/merge-status"} {
+        "merge "
+        span class={"status " (status_class(status))} { (status.as_str()) }
+      }
+      " - " (scripts.len()) " merged scripts, " (manifest.merged_at)
+    }
+  }
+}
+
+fn get_merge_inventory_view(
+  modlist_name: &str, inventory: &MergeInventory, help: &str,
+) -> maud::Markup {
+  let stale = inventory.stale_count();
+
+  html! {
+    div.small.center.summary title=(help) {
+      a href={"/modlist/"(modlist_name)"/merge-status"} { "scriptmerger inventory" }
+      " - " (inventory.count(InventoryMergeKind::Script)) " script merges, "
+      (inventory.count(InventoryMergeKind::Bundle)) " bundle merges, "
+      span class={@if stale == 0 { "status up-to-date" } @else { "status stale" }} {
+        (stale) " stale"
+      }
+    }
+  }
+}
+
+fn status_class(status: MergeStatus) -> &'static str {
+  match status {
+    MergeStatus::UpToDate => "up-to-date",
+    MergeStatus::Stale => "stale",
+    MergeStatus::Orphaned => "orphaned",
+  }
+}
+
 fn get_stylesheet() -> String {
   "
+    .malformed-menu {
+      color: crimson;
+    }
+
+    .summary {
+      margin: .2em 0;
+    }
+
+    .status.stale {
+      color: orange;
+    }
+
+    .status.orphaned {
+      color: crimson;
+    }
+
     h1 {
       margin-bottom: 0;
     }
@@ -397,6 +632,17 @@ fn get_stylesheet() -> String {
       justify-content: space-between;
     }
 
+    section.dependents h2 {
+      text-align: center;
+      cursor: help;
+    }
+    section.dependents form {
+      text-align: center;
+    }
+    section.dependents .row span + span {
+      margin-left: 1em;
+    }
+
     .modlist-name + a {
       transition: 0.25s all;
       
@@ -526,28 +772,24 @@ fn get_modlist_folders_view(
 
   // when a modlist is packed it doesn't show all the mods but only the pack folder
   if !is_top_level {
-    match (&view_type, is_modlist_packed) {
-      (FolderViewType::Mods, true) => {
-        return html! {
-          ul class="folder-list" {
-            li {
-              h3.packed-folder { (components::modlist_link(&modlist.name)) span.small{" packed"} }
+    if let (FolderViewType::Mods, true) = (view_type, is_modlist_packed) {
+      return html! {
+        ul class="folder-list" {
+          li {
+            h3.packed-folder { (components::modlist_link(&modlist.name)) span.small{" packed"} }
 
-              ul class={"folder-list " (if !is_top_level {"hidden"} else {""})} {
-                li.folder-listing {
-                  (&modlist.packed_folder_name())
-                }
+            ul class={"folder-list " (if !is_top_level {"hidden"} else {""})} {
+              li.folder-listing {
+                (&modlist.packed_folder_name())
               }
             }
           }
         }
-      }
-
-      _ => {}
-    };
+      };
+    }
   }
 
-  let folder_root = get_folder_from_view_type(modlist, &view_type);
+  let folder_root = get_folder_from_view_type(modlist, view_type);
   let children_result = get_children_without_symlinks(&folder_root);
 
   if let Err(error) = children_result {
@@ -617,7 +859,12 @@ fn get_modlist_folders_view(
 
             FolderViewType::Menus => {
               @for child in children {
-                li class="folder-listing" { (components::menu_display(&child, &modlist.name)) }
+                @let error = child
+                  .ends_with(".xml")
+                  .then(|| menu_check::parse_menu(&folder_root.join(&child)).err())
+                  .flatten();
+
+                li class="folder-listing" { (components::menu_display(&child, &modlist.name, error.as_deref())) }
               }
             }
           }
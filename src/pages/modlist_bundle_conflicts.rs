@@ -0,0 +1,201 @@
+use crate::components;
+use crate::models::bundle_conflicts;
+use crate::utils::format_size;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let report = match bundle_conflicts::analyze(modlist_name) {
+    Ok(report) => report,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not analyze the bundles of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - bundle conflicts", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let merged = report
+    .conflicts
+    .iter()
+    .filter(|conflict| conflict.merged)
+    .count();
+
+  // only the xml files can be merged, as text
+  let mergeable = report
+    .conflicts
+    .iter()
+    .filter(|conflict| conflict.path.to_lowercase().ends_with(".xml"))
+    .count();
+
+  let merge_help = "
+merges the xml files the mods change, with the vanilla files as the base, and
+writes them to a merged bundle. The files two mods change on the same lines are
+not merged.
+  ";
+
+  let conflicts_help = "
+the files listed here are in the bundles of more than one mod of the modlist,
+imported mods included. The mods are listed in the order the game loads them,
+without a merge only the first one is used.
+
+a vanilla file is a file that replaces one from the bundles folder, while a new
+file is a file the mods add and that the game doesn't have. A merged file is in
+the merged bundle content of the modlist.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "bundle conflicts" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(conflicts_help) {
+      (report.files) " files in the bundles of " (report.bundle_mods) " mods, "
+      (report.conflicts.len()) " contested, "
+      (merged) " of them merged"
+    }
+
+    @if mergeable > 0 {
+      form.center method="post" action="/api/modlist/merge-bundles" {
+        input type="hidden" name="modlist_name" value=(modlist_name);
+        input type="submit" value="merge bundles" class="text-style" title=(merge_help);
+      }
+    }
+
+    @if !report.unreadable.is_empty() {
+      details.unreadable {
+        summary { (report.unreadable.len()) " bundles could not be read" }
+
+        ul {
+          @for (path, error) in &report.unreadable {
+            li {
+              span.path { (path.display()) }
+              span.small { " " (error) }
+            }
+          }
+        }
+      }
+    }
+
+    @if report.conflicts.is_empty() {
+      p.center { "no file is in the bundles of more than one mod" }
+    } @else {
+      table.conflicts {
+        thead {
+          tr {
+            th { "file" }
+            th { "kind" }
+            th { "mods" }
+          }
+        }
+        tbody {
+          @for conflict in &report.conflicts {
+            tr {
+              td.path { (conflict.path) }
+              td {
+                @if conflict.overrides_vanilla() {
+                  span.accent { "vanilla" }
+                } @else {
+                  span.small { "new" }
+                }
+
+                @if conflict.merged {
+                  br;
+                  span.small { "merged" }
+                }
+              }
+              td {
+                ul {
+                  @for bundle_mod in &conflict.mods {
+                    li {
+                      a href={"/modlist/" (bundle_mod.modlist) "/edit/mods/" (bundle_mod.mod_name)} { (bundle_mod.mod_name) }
+
+                      @if bundle_mod.modlist != modlist_name {
+                        span.small { " from " } (components::modlist_link(&bundle_mod.modlist))
+                      }
+
+                      span.small {
+                        " " (bundle_mod.bundle) ", "
+                        (format_size(bundle_mod.entry.size as u64))
+
+                        @if bundle_mod.entry.compressed_size != bundle_mod.entry.size {
+                          " (" (format_size(bundle_mod.entry.compressed_size as u64)) " compressed)"
+                        }
+
+                        ", " (bundle_mod.entry.compression)
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - bundle conflicts", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.conflicts {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.conflicts th {
+      text-align: left;
+      color: grey;
+      font-weight: normal;
+    }
+
+    table.conflicts td, table.conflicts th {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.conflicts tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    .path {
+      font-family: monospace;
+    }
+
+    table.conflicts ul, details.unreadable ul {
+      list-style: none;
+      margin: 0;
+      padding: 0;
+    }
+
+    details.unreadable {
+      margin: 1em auto;
+      width: fit-content;
+      font-size: 0.8em;
+      color: crimson;
+    }
+  "
+  .to_owned()
+}
@@ -0,0 +1,145 @@
+use crate::components;
+use crate::models::script_conflicts;
+use crate::pages::script_diff::diff_url;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let report = match script_conflicts::analyze(modlist_name) {
+    Ok(report) => report,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not analyze the scripts of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - conflicts", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let overrides = report
+    .conflicts
+    .iter()
+    .filter(|conflict| conflict.overrides_vanilla())
+    .count();
+
+  let conflicts_help = "
+the scripts listed here are in more than one mod of the modlist, imported mods
+included. The mods are listed in the order the game loads them, without a merge
+only the first one is used.
+
+a vanilla script is a script that replaces one from the content folder, while a
+new script is a file the mods add and that the game doesn't have.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "script conflicts" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(conflicts_help) {
+      (report.scripts) " scripts in " (report.script_mods) " mods, "
+      (report.conflicts.len()) " contested, "
+      (overrides) " of them override a vanilla script"
+    }
+
+    @if report.conflicts.is_empty() {
+      p.center { "no script is in more than one mod, there is nothing to merge" }
+    } @else {
+      table.conflicts {
+        thead {
+          tr {
+            th { "script" }
+            th { "kind" }
+            th { "mods" }
+          }
+        }
+        tbody {
+          @for conflict in &report.conflicts {
+            tr {
+              td.path { (conflict.script) }
+              td {
+                @if conflict.overrides_vanilla() {
+                  span.accent { "vanilla" }
+                } @else {
+                  span.small { "new" }
+                }
+              }
+              td {
+                ul {
+                  @for script_mod in &conflict.mods {
+                    li {
+                      a href={"/modlist/" (script_mod.modlist) "/edit/mods/" (script_mod.mod_name)} { (script_mod.mod_name) }
+
+                      @if script_mod.modlist != modlist_name {
+                        span.small { " from " } (components::modlist_link(&script_mod.modlist))
+                      }
+
+                      " "
+                      a.small href=(diff_url(&script_mod.modlist, Some(&script_mod.mod_name), &conflict.script)) { "diff" }
+                    }
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - conflicts", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.conflicts {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.conflicts th {
+      text-align: left;
+      color: grey;
+      font-weight: normal;
+    }
+
+    table.conflicts td, table.conflicts th {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.conflicts tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.conflicts td.path {
+      font-family: monospace;
+    }
+
+    table.conflicts ul {
+      list-style: none;
+      margin: 0;
+      padding: 0;
+    }
+  "
+  .to_owned()
+}
@@ -1,5 +1,7 @@
 use crate::components;
+use crate::models::database_index::walk_files;
 use crate::models::modlist::ModList;
+use crate::pages::script_diff::diff_url;
 
 use actix_web::HttpRequest;
 use actix_web::HttpResponse;
@@ -38,6 +40,19 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     .filter(|ml| modlist.name != ml.name)
     .collect::<Vec<ModList>>();
 
+  // the scripts of the mod, each of them can be compared to vanilla
+  let mut scripts = Vec::new();
+  if folder_type == "mods" {
+    let scripts_path = modlist
+      .mods_path()
+      .join(folder_name)
+      .join("content")
+      .join("scripts");
+
+    walk_files(&scripts_path, "", &mut scripts);
+    scripts.retain(|(_, script)| script.to_lowercase().ends_with(".ws"));
+  }
+
   let folder_type_singular_form = if folder_type.ends_with("s") {
     folder_type.trim_end_matches("s")
   } else {
@@ -115,6 +130,22 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
     }
 
+    @if !scripts.is_empty() {
+      section.scripts {
+        h3.center { "Scripts" }
+
+        ul {
+          @for (_, script) in &scripts {
+            li {
+              span.path { (script) }
+              " "
+              a.small href=(diff_url(&modlist.name, Some(folder_name), script)) { "diff" }
+            }
+          }
+        }
+      }
+    }
+
     style type="text/css" { (get_stylesheet()) }
   };
 
@@ -180,6 +211,18 @@ fn get_stylesheet() -> String {
       font-size: 150%;
     }
 
+    section.scripts ul {
+      list-style: none;
+      width: fit-content;
+      margin: auto;
+      padding: 0;
+      font-size: 0.8em;
+    }
+
+    section.scripts .path {
+      font-family: monospace;
+    }
+
   "
   .to_owned()
 }
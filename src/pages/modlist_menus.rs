@@ -0,0 +1,162 @@
+use crate::components;
+use crate::models::menu_check::{self, MenuCollision, MenuRef};
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::{html, Markup};
+
+fn menu_link(menu: &MenuRef, modlist_name: &str) -> Markup {
+  html! {
+    a href={"/modlist/" (menu.modlist) "/edit/menus/" (menu.name)} { (menu.name) }
+
+    @if menu.modlist != modlist_name {
+      span.small { " from " } (components::modlist_link(&menu.modlist))
+    }
+  }
+}
+
+fn collisions_table(title: &str, collisions: &[MenuCollision], modlist_name: &str) -> Markup {
+  html! {
+    h3.center { (title) }
+
+    @if collisions.is_empty() {
+      p.center.small { "none" }
+    } @else {
+      table.menus {
+        @for collision in collisions {
+          tr {
+            td.path { (collision.id) }
+            td {
+              ul {
+                @for menu in &collision.menus {
+                  li { (menu_link(menu, modlist_name)) }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let report = match menu_check::analyze(modlist_name) {
+    Ok(report) => report,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not analyze the menus of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - menus", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let unused_help = "
+no script of the mods of the modlist mentions one of the groups of these menus,
+and no mod has a file with the same name. The mod the menu was made for is
+probably not in the modlist anymore.
+  ";
+
+  let duplicates = report.duplicate_groups.len() + report.duplicate_vars.len();
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "menus" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small {
+      (report.menus) " menus, "
+      (report.malformed.len()) " malformed, "
+      (duplicates) " duplicate ids, "
+      (report.unused.len()) " unused"
+    }
+
+    h3.center { "malformed menus" }
+
+    @if report.malformed.is_empty() {
+      p.center.small { "none" }
+    } @else {
+      table.menus {
+        @for (menu, error) in &report.malformed {
+          tr {
+            td { (menu_link(menu, modlist_name)) }
+            td.error { (error) }
+          }
+        }
+      }
+    }
+
+    (collisions_table("duplicate groups", &report.duplicate_groups, modlist_name))
+    (collisions_table("duplicate variables", &report.duplicate_vars, modlist_name))
+
+    h3.center title=(unused_help) { "unused menus" }
+
+    @if report.unused.is_empty() {
+      p.center.small { "none" }
+    } @else {
+      ul.unused {
+        @for menu in &report.unused {
+          li { (menu_link(menu, modlist_name)) }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - menus", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.menus {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.menus td {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.menus tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.menus td.path {
+      font-family: monospace;
+    }
+
+    table.menus td.error {
+      color: crimson;
+    }
+
+    table.menus ul, ul.unused {
+      list-style: none;
+      margin: 0;
+      padding: 0;
+    }
+
+    ul.unused {
+      text-align: center;
+      font-size: 0.8em;
+    }
+  "
+  .to_owned()
+}
@@ -44,7 +44,17 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
       div.merge-started { "merging..." }
       div.merge-finished.hidden { "merge finished" }
+      div.merge-error.hidden {}
 
+      div.merge-progress.row.hidden {
+        span.progress-text {}
+        button.cancel.text-style title="stop the merge and restore the previous merged scripts" { "cancel" }
+      }
+
+      div.merge-auto-resolved.hidden title="these conflicts were resolved like in the previous merges, forget a resolution to be asked again in the next merge" {
+        div.small { "resolved like the previous merges:" }
+        ul {}
+      }
 
       style type="text/css" { (get_stylesheet()) }
       script type="text/javascript" { (maud::PreEscaped(get_javascript())) }
@@ -116,6 +126,64 @@ fn get_stylesheet() -> String {
       display: none;
     }
 
+    .merge-error {
+      position: fixed;
+      bottom: 1em;
+      left: 50%;
+      transform: translate(-50%, 0);
+      background: #171413;
+      padding: .5em 2em;
+      border: solid 1px crimson;
+      color: crimson;
+      z-index: 10;
+    }
+
+    .merge-error.hidden {
+      display: none;
+    }
+
+    .merge-progress {
+      position: fixed;
+      top: .5em;
+      right: 1em;
+      gap: 1em;
+      align-items: baseline;
+      font-size: .8em;
+      color: grey;
+      z-index: 10;
+    }
+
+    .merge-progress.hidden {
+      display: none;
+    }
+
+    .merge-auto-resolved {
+      position: fixed;
+      bottom: 1em;
+      right: 1em;
+      max-height: 30vh;
+      overflow: auto;
+      font-size: .8em;
+      background: #171413;
+      padding: .5em 1em;
+      z-index: 10;
+    }
+
+    .merge-auto-resolved.hidden {
+      display: none;
+    }
+
+    .merge-auto-resolved ul {
+      list-style: none;
+      margin: 0;
+      padding: 0;
+      font-family: monospace;
+    }
+
+    .merge-progress .progress-text {
+      font-family: monospace;
+    }
+
     button.resolve {
       position: fixed;
       font-size: 2.5em;
@@ -314,10 +382,13 @@ fn get_merge_conflict_view() -> maud::Markup {
 
 fn get_javascript() -> String {
   "
-  function openwebsocket() {
+  let current_socket = null;
+
+  function openwebsocket(session_id) {
     let socket = null;
     try {
-      socket = new WebSocket('ws://localhost:5001', 'rust-websocket');
+      socket = new WebSocket(`ws://${location.host}/api/merge/${session_id}/socket`);
+      current_socket = socket;
     } catch (exception) {
         console.error(exception);
     }
@@ -327,12 +398,39 @@ fn get_javascript() -> String {
     };
 
     socket.onopen = function(event) {
-        this.onclose = function(event) {};
+        // the session keeps going when the socket closes, so a new socket is
+        // opened and receives the pending conflicts again.
+        this.onclose = function(event) {
+          if (!merge_finished) {
+            setTimeout(() => openwebsocket(session_id), 1000);
+          }
+        };
 
         this.onmessage = function(event) {
           const data = JSON.parse(event.data);
 
+          if (data.error) {
+            return show_merge_error(data.error);
+          }
+
+          document.querySelector('.merge-error').classList.add('hidden');
+
+          if (data.cancelled) {
+            socket.close();
+
+            return cancel_socket_merging();
+          }
+
+          if (data.progress) {
+            show_progress(data.progress);
+          }
+
           if (!data.conflicts.length) {
+            // a message with only the progress, the merge goes on
+            if (data.progress) {
+              return;
+            }
+
             socket.close();
 
             return finish_socket_merging();
@@ -345,6 +443,70 @@ fn get_javascript() -> String {
     };
   }
 
+  function show_progress(progress) {
+    const $progress = document.querySelector('.merge-progress');
+    $progress.classList.remove('hidden');
+
+    const current = progress.current_file ? ` - ${progress.current_file}` : '';
+
+    $progress.querySelector('.progress-text').textContent =
+      `${progress.scanned}/${progress.scripts} scripts, `
+      + `${progress.merged_cleanly} merged cleanly, `
+      + `${progress.conflicts_pending} conflicts pending`
+      + current;
+
+    show_auto_resolved(progress.auto_resolved || []);
+  }
+
+  let auto_resolved_count = 0;
+
+  // lists the conflicts that were resolved with the resolutions of the previous
+  // merges, each of them links to its resolution so it can be revisited.
+  function show_auto_resolved(auto_resolved) {
+    auto_resolved_count = auto_resolved.length;
+
+    const $container = document.querySelector('.merge-auto-resolved');
+    $container.classList.toggle('hidden', !auto_resolved.length);
+
+    const modlist_name = location.pathname.split('/').slice(-2)[0];
+    const $list = $container.querySelector('ul');
+    $list.textContent = '';
+
+    for (const conflict of auto_resolved) {
+      const $link = document.createElement('a');
+      $link.href = `/modlist/${modlist_name}/resolutions#${conflict.hunk}`;
+      $link.target = '_blank';
+      $link.textContent = `${conflict.script} from ${conflict.mod_name}`;
+
+      const $item = document.createElement('li');
+      $item.appendChild($link);
+      $list.appendChild($item);
+    }
+  }
+
+  document.querySelector('.merge-progress button.cancel').onclick = e => {
+    if (current_socket !== null && current_socket.readyState === WebSocket.OPEN) {
+      current_socket.send('cancel');
+    }
+  };
+
+  // the resolutions were refused, the conflicts that were sent are still
+  // pending so they are shown again and can be resolved one more time.
+  function show_merge_error(error) {
+    document.querySelector('.merge-started').classList.add('hidden');
+
+    const $error = document.querySelector('.merge-error');
+    $error.textContent = error;
+    $error.classList.remove('hidden');
+
+    const conflict_resolvers = Array.from(document.querySelectorAll('.merge-conflict.custom'));
+    conflict_resolvers.forEach(node => node.classList.remove('to-recycle'));
+
+    if (conflict_resolvers.length) {
+      document.querySelector('button.resolve').classList.remove('hidden');
+    }
+  }
+
   function show_conflicts(conflict_data, socket) {
     console.log(conflict_data);
 
@@ -472,26 +634,42 @@ fn get_javascript() -> String {
     // }, 50);
   }
 
+  let merge_finished = false;
+
   function start_socket_merging() {
     const modlist_name = location.pathname.split('/').slice(-2)[0];
 
+    // starts the merge, or joins it if it is already running in another tab
     fetch('/api/modlist/merge-scripts', {
       method: 'POST',
       body: `modlist_name=${modlist_name}`,
       headers: {
           'Content-Type': 'application/x-www-form-urlencoded',
       },
-      method: 'post',
     })
-    .then(console.log)
-    .catch(console.log);
-  
-    setTimeout(() => {
-      openwebsocket();
-    }, 50);
+    .then(response => {
+      if (!response.ok) {
+        return response.text().then(text => Promise.reject(text));
+      }
+
+      return response.text();
+    })
+    .then(session_id => openwebsocket(session_id))
+    .catch(error => show_merge_error(error));
+  }
+
+  function cancel_socket_merging() {
+    merge_finished = true;
+    document.querySelector('.merge-finished').textContent = 'merge cancelled';
+
+    finish_socket_merging();
   }
 
   function finish_socket_merging() {
+    merge_finished = true;
+    document.querySelector('.merge-started').classList.add('hidden');
+    document.querySelector('.merge-progress').classList.add('hidden');
+    document.querySelector('button.resolve').classList.add('hidden');
     document.querySelector('.merge-finished').classList.remove('hidden');
 
     Array.from(document.querySelectorAll('.merge-conflict.custom'))
@@ -500,6 +678,12 @@ fn get_javascript() -> String {
     document.querySelector('.merge-conflict-view .filename').textContent = '';
     document.querySelector('.merge-conflict-view .conflict-count').textContent = '';
 
+    // the page stays open so the conflicts that were resolved automatically can
+    // be reviewed.
+    if (auto_resolved_count) {
+      return;
+    }
+
     setTimeout(() => {
       // remove the last portion of the url
       location.href = location.href.split('/').slice(0, -1).join('/');
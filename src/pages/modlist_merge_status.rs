@@ -0,0 +1,196 @@
+use crate::components;
+use crate::models::merge_inventory::{self, InventoryMergeKind, MergeInventory};
+use crate::models::merge_manifest::{MergeManifest, MergeStatus};
+use crate::models::modlist::ModList;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::{html, Markup};
+
+fn status_class(status: MergeStatus) -> &'static str {
+  match status {
+    MergeStatus::UpToDate => "up-to-date",
+    MergeStatus::Stale => "stale",
+    MergeStatus::Orphaned => "orphaned",
+  }
+}
+
+fn merge_status_view(manifest: &MergeManifest, help: &str) -> Markup {
+  let (status, scripts) = manifest.status();
+
+  html! {
+    h3.center title=(help) {
+      "merge "
+      span class={"status " (status_class(status))} { (status.as_str()) }
+    }
+
+    p.center.small {
+      (scripts.len()) " merged scripts, " (manifest.merged_at)
+    }
+
+    table.merges {
+      @for (script, script_status) in &scripts {
+        tr {
+          td.path { (script.script) }
+          td class={"status " (status_class(*script_status))} { (script_status.as_str()) }
+          td {
+            @for source in script.original.iter().chain(script.sources.iter()) {
+              @let source_status = source.status();
+
+              @if source_status != MergeStatus::UpToDate {
+                div.small {
+                  (source.mod_name) " from " (source.modlist) " is " (source_status.as_str())
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+fn merge_inventory_view(inventory: &MergeInventory, help: &str) -> Markup {
+  let stale = inventory.stale_count();
+
+  html! {
+    h3.center title=(help) { "scriptmerger inventory" }
+
+    p.center.small {
+      (inventory.count(InventoryMergeKind::Script)) " script merges, "
+      (inventory.count(InventoryMergeKind::Bundle)) " bundle merges, "
+      span class={@if stale == 0 { "status up-to-date" } @else { "status stale" }} {
+        (stale) " stale"
+      }
+    }
+
+    table.merges {
+      @for merge in &inventory.merges {
+        tr {
+          td.small { (merge.kind.as_str()) }
+          td.path { (merge.path) }
+          td.small { (merge.merged_mod) }
+          td.small {
+            @for (i, mod_name) in merge.included_mods.iter().enumerate() {
+              @if i > 0 { ", " }
+
+              @if merge.missing_mods.contains(mod_name) {
+                span.status.orphaned title="this mod is not in the modlist anymore" { (mod_name) }
+              } @else {
+                (mod_name)
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let modlist = match ModList::get_by_name(modlist_name) {
+    Some(modlist) => modlist,
+    None => {
+      let content = html! {
+        h1 { "no such modlist" }
+      };
+      let view = components::page(&format!("{} - merge status", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let merge_manifest = MergeManifest::read_from_disk(&modlist).ok().flatten();
+  let inventory = merge_inventory::analyze(&modlist.name);
+
+  let merge_status_help = "
+whether the merged scripts still match the files they were merged from. A merge
+is stale when one of the files changed since, and orphaned when one of them is
+gone. Merge the modlist again in both cases.
+  ";
+
+  let inventory_help = "
+the merges the scriptmerger keeps in the MergeInventory.xml file of the modlist,
+with the mods included in each of them. A merge is stale when one of its mods
+is not in the modlist anymore, merge the modlist again in that case.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "merge status" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    @match &merge_manifest {
+      Some(manifest) => (merge_status_view(manifest, merge_status_help)),
+      None => {
+        h3.center title=(merge_status_help) { "merge" }
+        p.center.small { "the modlist was not merged" }
+      }
+    }
+
+    @match &inventory {
+      Ok(inventory) => {
+        @if inventory.merges.is_empty() {
+          h3.center title=(inventory_help) { "scriptmerger inventory" }
+          p.center.small { "none" }
+        } @else {
+          (merge_inventory_view(inventory, inventory_help))
+        }
+      }
+      Err(error) => {
+        h3.center title=(inventory_help) { "scriptmerger inventory" }
+        p.center.small.status.orphaned {
+          "the merge inventory could not be read: " (error)
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - merge status", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.merges {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.merges td {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.merges tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.merges td.path {
+      font-family: monospace;
+    }
+
+    .status.stale {
+      color: orange;
+    }
+
+    .status.orphaned {
+      color: crimson;
+    }
+  "
+  .to_owned()
+}
@@ -0,0 +1,140 @@
+use crate::components;
+use crate::models::modlist::ModList;
+use crate::models::mods_settings;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let priorities = ModList::get_by_name(modlist_name)
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such modlist"))
+    .and_then(|modlist| mods_settings::get_mod_priorities(&modlist));
+
+  let priorities = match priorities {
+    Ok(priorities) => priorities,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read the mods settings of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - mod priorities", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let priorities_help = "
+the priorities and enabled states written in the mods.settings file of the saves
+of the modlist. The mods with the lowest priority load first and their files win
+over the other mods. The values follow the load order of the modlist, the merges
+first then the mods in alphabetical order, unless they are edited here. The
+entries of the file for the mods that are not in the modlist are kept.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "mod priorities" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(priorities_help) {
+      (priorities.len()) " mods, "
+      (priorities.iter().filter(|mod_priority| mod_priority.edited).count()) " edited"
+    }
+
+    form method="post" action="/api/modlist/mods-settings" {
+      input type="hidden" name="modlist_name" value=(modlist_name);
+
+      table.priorities {
+        thead {
+          tr {
+            th { "mod" }
+            th { "enabled" }
+            th { "priority" }
+          }
+        }
+        tbody {
+          @for mod_priority in &priorities {
+            @let enabled_name = format!("enabled|{}", mod_priority.mod_name);
+
+            tr.edited[mod_priority.edited] {
+              td {
+                (mod_priority.mod_name)
+
+                @if mod_priority.modlist != modlist_name {
+                  span.small { " from " } (components::modlist_link(&mod_priority.modlist))
+                }
+              }
+              td {
+                // the hidden input is sent when the box is not checked
+                input type="hidden" name=(enabled_name) value="0";
+
+                @if mod_priority.enabled {
+                  input type="checkbox" name=(enabled_name) value="1" checked;
+                } @else {
+                  input type="checkbox" name=(enabled_name) value="1";
+                }
+              }
+              td {
+                input type="number" min="0" name={"priority|" (mod_priority.mod_name)} value=(mod_priority.priority);
+              }
+            }
+          }
+        }
+      }
+
+      div.row.center {
+        input type="submit" value="save" class="text-style";
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - mod priorities", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.priorities {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.priorities th {
+      text-align: left;
+      color: grey;
+      font-weight: normal;
+    }
+
+    table.priorities td, table.priorities th {
+      padding: .2em 1em;
+    }
+
+    table.priorities tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.priorities tr.edited td:first-child {
+      color: var(--var-color-accent);
+    }
+
+    table.priorities input[type=number] {
+      width: 5em;
+    }
+  "
+  .to_owned()
+}
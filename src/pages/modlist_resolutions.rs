@@ -0,0 +1,120 @@
+use crate::components;
+use crate::models::merge_resolutions::MergeResolutions;
+use crate::models::modlist::ModList;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let resolutions = ModList::get_by_name(modlist_name)
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such modlist"))
+    .and_then(|modlist| MergeResolutions::read_from_disk(&modlist));
+
+  let resolutions = match resolutions {
+    Ok(resolutions) => resolutions,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read the resolutions of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - resolutions", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let resolutions_help = "
+the resolutions you gave to the conflicts of the previous merges. When the same
+conflict shows up in a merge it is resolved the same way without asking.
+
+forgetting a resolution makes the next merge ask for it again.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "remembered resolutions" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(resolutions_help) {
+      (resolutions.resolutions.len()) " resolutions"
+    }
+
+    @for resolution in &resolutions.resolutions {
+      div.resolution id=(resolution.hunk) {
+        div.row.baseline {
+          span.path { (resolution.script) }
+          span.small { " from " (resolution.mod_name) }
+
+          form method="post" action="/api/modlist/forget-resolution" {
+            input type="hidden" name="modlist_name" value=(modlist_name);
+            input type="hidden" name="script" value=(resolution.script);
+            input type="hidden" name="mod_name" value=(resolution.mod_name);
+            input type="hidden" name="hunk" value=(resolution.hunk);
+            input type="submit" value="forget" class="text-style";
+          }
+        }
+
+        div.versions {
+          pre.conflict { (resolution.conflict) }
+          pre.resolved { (resolution.resolution) }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - resolutions", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    .resolution {
+      margin: 2em auto;
+      font-size: 0.8em;
+    }
+
+    .resolution .row {
+      gap: 1em;
+    }
+
+    .resolution .path {
+      font-family: monospace;
+    }
+
+    .resolution:target .path {
+      color: var(--var-color-accent);
+    }
+
+    .resolution .versions {
+      display: flex;
+      gap: 1em;
+    }
+
+    .resolution pre {
+      flex: 1;
+      margin: 0;
+      padding: .5em;
+      overflow: auto;
+      border: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    .resolution pre.conflict {
+      color: grey;
+    }
+  "
+  .to_owned()
+}
@@ -0,0 +1,178 @@
+use crate::components;
+use crate::models::string_conflicts;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+/// the number of colliding ids listed for every collision
+const LISTED_IDS: usize = 50;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let report = match string_conflicts::analyze(modlist_name) {
+    Ok(report) => report,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not analyze the strings of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - string ids", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let collisions_help = "
+the string ids listed here are used by more than one mod of the modlist for the
+same language, imported mods included. Only one of the texts is shown in game,
+the other mods display a wrong text. The mods must use different ids.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "string ids" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(collisions_help) {
+      (report.string_mods) " mods with w3strings files, "
+      (report.collisions.len()) " collisions"
+    }
+
+    @if !report.unreadable.is_empty() {
+      details.unreadable {
+        summary { (report.unreadable.len()) " w3strings files could not be read" }
+
+        ul {
+          @for (path, error) in &report.unreadable {
+            li {
+              span.path { (path.display()) }
+              span.small { " " (error) }
+            }
+          }
+        }
+      }
+    }
+
+    @if report.collisions.is_empty() {
+      p.center { "no string id is used by more than one mod" }
+    } @else {
+      table.collisions {
+        thead {
+          tr {
+            th { "language" }
+            th { "mods" }
+            th { "ids" }
+          }
+        }
+        tbody {
+          @for collision in &report.collisions {
+            @let (first, last) = collision.range();
+
+            tr {
+              td { (collision.language) }
+              td {
+                ul {
+                  @for string_mod in &collision.mods {
+                    li {
+                      a href={"/modlist/" (string_mod.modlist) "/edit/mods/" (string_mod.mod_name)} { (string_mod.mod_name) }
+
+                      @if string_mod.modlist != modlist_name {
+                        span.small { " from " } (components::modlist_link(&string_mod.modlist))
+                      }
+                    }
+                  }
+                }
+              }
+              td {
+                details {
+                  summary {
+                    (collision.ids.len()) " ids, from " (first) " to " (last)
+
+                    @if collision.keyed > 0 {
+                      span.small { ", " (collision.keyed) " with a key" }
+                    }
+                  }
+
+                  p.path {
+                    @for (i, id) in collision.ids.iter().take(LISTED_IDS).enumerate() {
+                      @if i > 0 { ", " }
+                      (id)
+                    }
+
+                    @if collision.ids.len() > LISTED_IDS {
+                      ", …"
+                    }
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - string ids", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.collisions {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.collisions th {
+      text-align: left;
+      color: grey;
+      font-weight: normal;
+    }
+
+    table.collisions td, table.collisions th {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.collisions tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.collisions p.path {
+      max-width: 40em;
+      margin: .2em 0;
+    }
+
+    .path {
+      font-family: monospace;
+    }
+
+    table.collisions ul, details.unreadable ul {
+      list-style: none;
+      margin: 0;
+      padding: 0;
+    }
+
+    details.unreadable {
+      margin: 1em auto;
+      width: fit-content;
+      font-size: 0.8em;
+      color: crimson;
+    }
+  "
+  .to_owned()
+}
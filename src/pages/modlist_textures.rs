@@ -0,0 +1,121 @@
+use crate::components;
+use crate::models::texture_conflicts;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let report = match texture_conflicts::analyze(modlist_name) {
+    Ok(report) => report,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not analyze the textures of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - textures", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let texture_overrides_help = "
+the textures that are in the texture cache of more than one mod. The game uses
+the texture of the first mod in the load order, the other mods are overridden.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "textures" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(texture_overrides_help) {
+      (report.textures) " textures in " (report.texture_mods) " mods, "
+      span class={@if report.overrides.is_empty() { "status up-to-date" } @else { "status stale" }} {
+        (report.overrides.len()) " overridden"
+      }
+    }
+
+    @for (path, error) in &report.unreadable {
+      p.center.small.status.orphaned { (path.display()) " could not be read: " (error) }
+    }
+
+    @if report.overrides.is_empty() {
+      p.center.small { "no texture is overridden" }
+    } @else {
+      table.textures {
+        @for texture in &report.overrides {
+          @let winner = texture.winner();
+
+          tr {
+            td.path { (texture.name) }
+            td {
+              (winner.mod_name)
+
+              @if winner.modlist != modlist_name {
+                span.small { " from " } (components::modlist_link(&winner.modlist))
+              }
+
+              span.small { " " (winner.entry.dimensions()) }
+            }
+            td.small {
+              "overrides "
+              @for (i, texture_mod) in texture.mods.iter().skip(1).enumerate() {
+                @if i > 0 { ", " }
+                span title=(texture_mod.entry.dimensions()) { (texture_mod.mod_name) }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - textures", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.textures {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.textures td {
+      padding: .2em 1em;
+      vertical-align: top;
+    }
+
+    table.textures tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.textures td.path {
+      font-family: monospace;
+    }
+
+    .status.stale {
+      color: orange;
+    }
+
+    .status.orphaned {
+      color: crimson;
+    }
+  "
+  .to_owned()
+}
@@ -0,0 +1,202 @@
+use crate::components;
+use crate::models::menu_check::{MenuDisplayType, MenuGroup, MenuVar};
+use crate::models::modlist::ModList;
+use crate::models::settings_file::SettingsFile;
+use crate::models::user_settings::{self, MenuSettings};
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::{html, Markup};
+
+/// the name of the form field of the variable, see
+/// [`crate::api::modlist::update_user_settings`].
+fn field_name(prefix: &str, group: &MenuGroup, var: &MenuVar) -> String {
+  format!("{}|{}|{}", prefix, group.id, var.id)
+}
+
+fn var_input(group: &MenuGroup, var: &MenuVar, value: &str) -> Markup {
+  let name = field_name("value", group, var);
+
+  html! {
+    @match &var.display_type {
+      MenuDisplayType::Slider { min, max, steps } => {
+        @let step = if *steps > 0 { (max - min) / *steps as f64 } else { 1.0 };
+
+        input type="number" name=(name) value=(value) min=(min) max=(max) step=(step);
+      }
+      MenuDisplayType::Toggle => {
+        // the hidden input is sent when the box is not checked
+        input type="hidden" name=(name) value="false";
+
+        @if value.eq_ignore_ascii_case("true") {
+          input type="checkbox" name=(name) value="true" checked;
+        } @else {
+          input type="checkbox" name=(name) value="true";
+        }
+      }
+      MenuDisplayType::Options(options) => {
+        select name=(name) {
+          @for (id, display_name) in options {
+            @if id == value {
+              option value=(id) selected { (display_name) }
+            } @else {
+              option value=(id) { (display_name) }
+            }
+          }
+        }
+      }
+      MenuDisplayType::Separator => {}
+      MenuDisplayType::Other(display_type) => {
+        input type="text" name=(name) value=(value) title=(display_type);
+      }
+    }
+  }
+}
+
+fn group_view(
+  modlist_name: &str, menu: &MenuSettings, group: &MenuGroup, settings: &SettingsFile,
+) -> Markup {
+  let has_values = settings.sections.iter().any(|(name, _)| name == &group.id);
+
+  html! {
+    div.group {
+      div.row.baseline {
+        h3 title=(group.id) { (group.display_name) }
+        span.small { (menu.menu.name) }
+
+        @if menu.menu.modlist != modlist_name {
+          span.small { " from " } (components::modlist_link(&menu.menu.modlist))
+        }
+
+        @if has_values {
+          form method="post" action="/api/modlist/user-settings-reset" {
+            input type="hidden" name="modlist_name" value=(modlist_name);
+            input type="hidden" name="group" value=(group.id);
+            input type="submit" value="reset" class="text-style" title="removes the values of the group, the game uses the default values of the menu";
+          }
+        }
+      }
+
+      form method="post" action="/api/modlist/user-settings" {
+        input type="hidden" name="modlist_name" value=(modlist_name);
+
+        table.vars {
+          @for var in &group.vars {
+            @if let MenuDisplayType::Separator = var.display_type {
+              tr.separator { td colspan="2" {} }
+            } @else {
+              @let value = settings
+                .get(&group.id, &var.id)
+                .map(String::from)
+                .unwrap_or_else(|| var.display_type.default_value());
+
+              tr {
+                td title=(var.id) { (var.display_name) }
+                td {
+                  input type="hidden" name=(field_name("original", group, var)) value=(value);
+                  (var_input(group, var, &value))
+                }
+              }
+            }
+          }
+        }
+
+        input type="submit" value="save" class="text-style";
+      }
+    }
+  }
+}
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let data = ModList::get_by_name(modlist_name)
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such modlist"))
+    .and_then(|modlist| user_settings::read_user_settings(&modlist))
+    .and_then(|settings| Ok((settings, user_settings::get_mod_menus(modlist_name)?)));
+
+  let (settings, menus) = match data {
+    Ok(data) => data,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read the settings of " (modlist_name) }
+        p { (error) }
+      };
+      let view = components::page(&format!("{} - settings", modlist_name), &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let settings_help = "
+the options of the menus of the mods, with their values from the user.settings
+file in the saves of the modlist. The values the file doesn't have yet are the
+ones the menu shows by default. The game must be closed while the values are
+saved, it writes the file again when it exits.
+  ";
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "mod settings" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.small title=(settings_help) {
+      (menus.len()) " mod menus"
+    }
+
+    @for menu in &menus {
+      @for group in &menu.groups {
+        // the groups with only separators have nothing to edit
+        @if group.vars.iter().any(|var| !matches!(var.display_type, MenuDisplayType::Separator)) {
+          (group_view(modlist_name, menu, group, &settings))
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - settings", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    .group {
+      margin: 2em auto;
+      width: fit-content;
+      font-size: 0.8em;
+    }
+
+    .group .row {
+      gap: 1em;
+    }
+
+    .group h3 {
+      margin: .5em 0;
+    }
+
+    table.vars {
+      border-collapse: collapse;
+      margin-bottom: .5em;
+    }
+
+    table.vars td {
+      padding: .2em 1em;
+    }
+
+    table.vars tr.separator td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+  "
+  .to_owned()
+}
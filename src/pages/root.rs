@@ -1,4 +1,5 @@
 use crate::components;
+use crate::models::database_config::DatabaseConfig;
 use crate::models::modlist::ModList;
 
 use actix_web::HttpRequest;
@@ -9,21 +10,12 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
   let query = req.query_string();
   let query = qstring::QString::from(query);
 
-  let visibility = query.get("visibility").and_then(|n| n.parse::<i64>().ok());
-
-  let mut modlists = match visibility {
-    Some(v) => ModList::get_all()
-      .iter()
-      .map(ModList::read_metadata_from_disk_copy)
-      .filter(Result::is_ok)
-      .map(Result::unwrap)
-      .filter(|modlist| modlist.visibility == v)
-      .collect(),
-    None => ModList::get_all(),
-  };
+  let group_filter = query.get("group");
+
+  let mut modlists = ModList::get_all();
 
-  for i in 0..modlists.len() {
-    if let Err(error) = modlists[i].read_metadata_from_disk() {
+  for modlist in modlists.iter_mut() {
+    if let Err(error) = modlist.read_metadata_from_disk() {
       let content = html! {
         h1 { "Could not read modlist metadata" }
         p { (error) }
@@ -36,37 +28,34 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     }
   }
 
-  let mut installable_modlists = Vec::new();
-  let mut installable_modlists_visibility_levels = std::collections::HashSet::new();
-
-  for i in 0..modlists.len() {
-    if modlists[i].name == "vanilla" || modlists[i].has_modlist_imported("vanilla") {
-      installable_modlists.push(i);
-      installable_modlists_visibility_levels.insert(modlists[i].visibility);
-    }
+  if let Some(group) = group_filter {
+    modlists.retain(|modlist| modlist.group.as_deref() == Some(group));
   }
 
-  let mut shared_modlists = Vec::new();
-  let mut shared_modlists_visibility_levels = std::collections::HashSet::new();
+  let config = match DatabaseConfig::read_from_disk() {
+    Ok(config) => config,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not read the database config" }
+        p { (error) }
+      };
+      let view = components::page("root", &content);
 
-  for i in 0..modlists.len() {
-    if modlists[i].name != "vanilla" && !modlists[i].has_modlist_imported("vanilla") {
-      shared_modlists.push(i);
-      shared_modlists_visibility_levels.insert(modlists[i].visibility);
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
     }
-  }
+  };
 
-  let mut shared_levels = shared_modlists_visibility_levels
-    .into_iter()
-    .collect::<Vec<i64>>();
-  shared_levels.sort();
-  shared_levels.reverse();
+  let installable_modlists = modlists
+    .iter()
+    .filter(|modlist| modlist.is_installable())
+    .collect::<Vec<&ModList>>();
 
-  let mut installable_levels = installable_modlists_visibility_levels
-    .into_iter()
-    .collect::<Vec<i64>>();
-  installable_levels.sort();
-  installable_levels.reverse();
+  let shared_modlists = modlists
+    .iter()
+    .filter(|modlist| !modlist.is_installable())
+    .collect::<Vec<&ModList>>();
 
   // if there is no vanilla modlist, force a call to initialize
   let should_initialize = ModList::get_by_name("vanilla").is_none();
@@ -80,64 +69,26 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
         }
       }
       @else {
-        // div class="row flex-center" {
-        //   a href={"?visibility=" (visibility_down)} { "<" }
-        //   span title="This allows you to navigate through modlists with different visibility levels" { (visibility) };
-        //   a href={"?visibility=" (visibility_up)} { ">" }
-        // }
+        (components::search_form(""))
+
+        @if let Some(group) = group_filter {
+          div class="row flex-center" {
+            span { "group " span.accent { (group) } " - " a href="/" { "show all" } }
+          }
+        }
 
         div class="modlist-containers" {
 
           div class="column" {
             h2 { "Shared modlists" }
 
-            ul class="level-list" {
-              @for level in &shared_levels {
-                li class="level-listing" {
-                  h3 { (level) }
-
-                  ul {
-                    @for index in &shared_modlists {
-                      @if &modlists[*index].visibility == level {
-                        li class="modlist" {
-                          a title="you cannot install this modlist because it doesn't import the vanilla modlist" href={"/modlist/" (&modlists[*index].name)} { (&modlists[*index].name) }
-                        }
-                      }
-                    }
-                  }
-                }
-              }
-            }
+            (get_groups_view(&config, &shared_modlists))
           }
 
           div class="column" {
             h2 { "Installable modlists" }
 
-            ul class="level-list" {
-              @for level in &installable_levels {
-                li class="level-listing" {
-                  h3 { (level) }
-
-                  ul {
-                    @for index in &installable_modlists {
-                      @if &modlists[*index].visibility == level {
-                        li class="modlist" {
-                          a href={"/modlist/" (&modlists[*index].name)} { (&modlists[*index].name) }
-
-                          @if &modlists[*index].name == "vanilla" || modlists[*index].has_modlist_imported("vanilla") {
-                            form method="post" action="/api/modlist/install" {
-                              input type="hidden" name="name" value=(&modlists[*index].name);
-
-                              input type="submit" value="install";
-                            }
-                          }
-                        }
-                      }
-                    }
-                  }
-                }
-              }
-            }
+            (get_groups_view(&config, &installable_modlists))
           }
 
           div class="column" {
@@ -146,6 +97,10 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
               input type="text" name="modlist_name" placeholder="modlist's name";
               input type="submit" value="new";
             }
+
+            a class="small" href="/graph" { "import graph" }
+            br;
+            a class="small" href="/groups" { "groups" }
           }
 
         }
@@ -162,6 +117,84 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     .body(view.into_string())
 }
 
+/// lists the modlists under their groups, in the order of the groups in the
+/// database config. The modlists without a group, or with a group that doesn't
+/// exist, are listed last.
+fn get_groups_view(config: &DatabaseConfig, modlists: &[&ModList]) -> maud::Markup {
+  let ungrouped_modlists = modlists
+    .iter()
+    .filter(|modlist| match &modlist.group {
+      Some(name) => !config.has_group(name),
+      None => true,
+    })
+    .collect::<Vec<_>>();
+
+  html! {
+    ul class="level-list" {
+      @for group in &config.groups {
+        @let group_modlists = modlists
+          .iter()
+          .filter(|modlist| modlist.group.as_deref() == Some(group.name.as_str()))
+          .collect::<Vec<_>>();
+
+        @if !group_modlists.is_empty() {
+          li class="level-listing" {
+            h3 class="row baseline" {
+              form method="post" action="/api/group/toggle-collapse" {
+                input type="hidden" name="group_name" value=(group.name);
+
+                @if group.collapsed {
+                  input type="submit" class="text-style" value="[+]";
+                } @else {
+                  input type="submit" class="text-style" value="[-]";
+                }
+              }
+
+              a title=(group.description) href={"/?group=" (group.name)} { (group.name) }
+            }
+
+            ul class={"modlists " (if group.collapsed {"hidden"} else {""})} {
+              @for modlist in group_modlists {
+                (get_modlist_view(modlist))
+              }
+            }
+          }
+        }
+      }
+
+      @if !ungrouped_modlists.is_empty() {
+        li class="level-listing" {
+          h3 { "ungrouped" }
+
+          ul class="modlists" {
+            @for modlist in ungrouped_modlists {
+              (get_modlist_view(modlist))
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+fn get_modlist_view(modlist: &ModList) -> maud::Markup {
+  html! {
+    li class="modlist" {
+      @if modlist.is_installable() {
+        a href={"/modlist/" (&modlist.name)} { (&modlist.name) }
+
+        form method="post" action="/api/modlist/install" {
+          input type="hidden" name="name" value=(&modlist.name);
+
+          input type="submit" value="install";
+        }
+      } @else {
+        a title="you cannot install this modlist because it doesn't import the vanilla modlist" href={"/modlist/" (&modlist.name)} { (&modlist.name) }
+      }
+    }
+  }
+}
+
 fn get_stylesheet() -> String {
   "
     ul {
@@ -191,6 +224,15 @@ fn get_stylesheet() -> String {
       margin: 0;
     }
 
+    .modlist-containers .column .level-list h3 a {
+      color: white;
+    }
+
+    .modlist-containers .column .level-list h3 form input {
+      margin: 0;
+      padding: 0 .4em 0 0;
+    }
+
     .modlist-containers .column .level-listing ul {
       padding-left: 1em;
       margin-left: 5px;
@@ -0,0 +1,448 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::components;
+use crate::models::modlist::ModList;
+use crate::models::script_merge::read_script;
+use crate::utils::diff3::{diff, normalize_line_endings, DiffLine};
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+/// the number of unchanged lines shown around the changes
+const CONTEXT_LINES: usize = 3;
+
+/// a row of the diff, with the line numbers in the vanilla and in the compared
+/// script.
+struct DiffRow<'a> {
+  vanilla_number: Option<usize>,
+  number: Option<usize>,
+  line: DiffLine<'a>,
+}
+
+/// a part of the diff to display, the unchanged lines far from the changes are
+/// folded.
+enum DiffHunk<'a> {
+  Rows(Vec<DiffRow<'a>>),
+  Folded(usize),
+}
+
+/// the url of the diff of a script, `mod_name` is `None` for a script of the
+/// `content` folder.
+pub fn diff_url(modlist_name: &str, mod_name: Option<&str>, script: &str) -> String {
+  let mut query = qstring::QString::new(vec![("script", script)]);
+
+  if let Some(mod_name) = mod_name {
+    query.add_pair(("mod_name", mod_name));
+  }
+
+  format!("/modlist/{}/diff?{}", modlist_name, query)
+}
+
+/// joins the path of the script to the folder, the script must stay in it
+fn join_script(folder: PathBuf, script: &str) -> Option<PathBuf> {
+  script
+    .split('/')
+    .filter(|component| !component.is_empty())
+    .try_fold(folder, |path, component| match component {
+      "." | ".." => None,
+      component if component.contains('\\') => None,
+      component => Some(path.join(component)),
+    })
+}
+
+/// whether the name is a single folder name, so joining it to a folder can't
+/// leave the folder
+fn is_plain_name(name: &str) -> bool {
+  let mut components = Path::new(name).components();
+
+  matches!(
+    (components.next(), components.next()),
+    (Some(Component::Normal(_)), None)
+  ) && !name.contains(['/', '\\'])
+}
+
+fn number_rows(lines: Vec<DiffLine>) -> Vec<DiffRow> {
+  let mut vanilla_number = 0;
+  let mut number = 0;
+
+  lines
+    .into_iter()
+    .map(|line| {
+      let (in_vanilla, in_script) = match line {
+        DiffLine::Same(_) => (true, true),
+        DiffLine::Removed(_) => (true, false),
+        DiffLine::Added(_) => (false, true),
+      };
+
+      vanilla_number += in_vanilla as usize;
+      number += in_script as usize;
+
+      DiffRow {
+        vanilla_number: in_vanilla.then_some(vanilla_number),
+        number: in_script.then_some(number),
+        line,
+      }
+    })
+    .collect()
+}
+
+/// folds the unchanged lines that are more than `CONTEXT_LINES` away from a
+/// change.
+fn fold_rows(rows: Vec<DiffRow>) -> Vec<DiffHunk> {
+  let changed = rows
+    .iter()
+    .map(|row| !matches!(row.line, DiffLine::Same(_)))
+    .collect::<Vec<_>>();
+
+  let is_visible = |i: usize| {
+    let start = i.saturating_sub(CONTEXT_LINES);
+    let end = (i + CONTEXT_LINES + 1).min(changed.len());
+
+    changed[start..end].iter().any(|changed| *changed)
+  };
+
+  let mut hunks = Vec::new();
+  let mut current = Vec::new();
+  let mut folded = 0;
+
+  for (i, row) in rows.into_iter().enumerate() {
+    if is_visible(i) {
+      if folded > 0 {
+        hunks.push(DiffHunk::Folded(folded));
+        folded = 0;
+      }
+
+      current.push(row);
+    } else {
+      if !current.is_empty() {
+        hunks.push(DiffHunk::Rows(std::mem::take(&mut current)));
+      }
+
+      folded += 1;
+    }
+  }
+
+  if !current.is_empty() {
+    hunks.push(DiffHunk::Rows(current));
+  }
+
+  if folded > 0 {
+    hunks.push(DiffHunk::Folded(folded));
+  }
+
+  hunks
+}
+
+fn line_text<'a>(line: &DiffLine<'a>) -> &'a str {
+  match line {
+    DiffLine::Same(text) | DiffLine::Removed(text) | DiffLine::Added(text) => {
+      text.trim_end_matches(&['\r', '\n'][..])
+    }
+  }
+}
+
+fn render_unified(hunks: &[DiffHunk]) -> maud::Markup {
+  html! {
+    table.diff.unified {
+      @for hunk in hunks {
+        @match hunk {
+          DiffHunk::Folded(count) => {
+            tr.folded { td colspan="4" { "… " (count) " unchanged lines" } }
+          }
+          DiffHunk::Rows(rows) => {
+            @for row in rows {
+              @let (class, sign) = match row.line {
+                DiffLine::Same(_) => ("same", " "),
+                DiffLine::Removed(_) => ("removed", "-"),
+                DiffLine::Added(_) => ("added", "+"),
+              };
+
+              tr class=(class) {
+                td.number { (row.vanilla_number.map(|n| n.to_string()).unwrap_or_default()) }
+                td.number { (row.number.map(|n| n.to_string()).unwrap_or_default()) }
+                td.sign { (sign) }
+                td.line { pre { (line_text(&row.line)) } }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/// pairs the removed and added lines of every change so they face each other
+fn render_split(hunks: &[DiffHunk]) -> maud::Markup {
+  let mut table_rows = Vec::new();
+
+  for hunk in hunks {
+    let rows = match hunk {
+      DiffHunk::Folded(count) => {
+        table_rows.push(html! {
+          tr.folded { td colspan="4" { "… " (count) " unchanged lines" } }
+        });
+
+        continue;
+      }
+      DiffHunk::Rows(rows) => rows,
+    };
+
+    let mut i = 0;
+    while i < rows.len() {
+      if let DiffLine::Same(_) = rows[i].line {
+        let row = &rows[i];
+
+        table_rows.push(html! {
+          tr.same {
+            td.number { (row.vanilla_number.unwrap_or_default()) }
+            td.line { pre { (line_text(&row.line)) } }
+            td.number { (row.number.unwrap_or_default()) }
+            td.line { pre { (line_text(&row.line)) } }
+          }
+        });
+
+        i += 1;
+        continue;
+      }
+
+      let change_end = rows[i..]
+        .iter()
+        .position(|row| matches!(row.line, DiffLine::Same(_)))
+        .map(|position| i + position)
+        .unwrap_or(rows.len());
+
+      let removed = rows[i..change_end]
+        .iter()
+        .filter(|row| matches!(row.line, DiffLine::Removed(_)))
+        .collect::<Vec<_>>();
+      let added = rows[i..change_end]
+        .iter()
+        .filter(|row| matches!(row.line, DiffLine::Added(_)))
+        .collect::<Vec<_>>();
+
+      for j in 0..removed.len().max(added.len()) {
+        let left = removed.get(j);
+        let right = added.get(j);
+
+        table_rows.push(html! {
+          tr {
+            @if let Some(row) = left {
+              td.number { (row.vanilla_number.unwrap_or_default()) }
+              td.line.removed { pre { (line_text(&row.line)) } }
+            } @else {
+              td.number {}
+              td.line.empty {}
+            }
+
+            @if let Some(row) = right {
+              td.number { (row.number.unwrap_or_default()) }
+              td.line.added { pre { (line_text(&row.line)) } }
+            } @else {
+              td.number {}
+              td.line.empty {}
+            }
+          }
+        });
+      }
+
+      i = change_end;
+    }
+  }
+
+  html! {
+    table.diff.split {
+      @for row in table_rows {
+        (row)
+      }
+    }
+  }
+}
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let modlist_name = req
+    .match_info()
+    .get("modlist_name")
+    .unwrap_or("__unknown__");
+
+  let query = qstring::QString::from(req.query_string());
+  let script = query.get("script").unwrap_or("").to_owned();
+  let mod_name = query.get("mod_name").map(String::from);
+  let is_split = query.get("view") == Some("split");
+
+  let error_page = |title: &str, error: &str| {
+    let content = html! {
+      h1 { (title) }
+      p { (error) }
+    };
+    let view = components::page(&format!("{} - diff", modlist_name), &content);
+
+    HttpResponse::Ok()
+      .content_type("text/html")
+      .body(view.into_string())
+  };
+
+  let modlist = match ModList::get_by_name(modlist_name) {
+    Some(modlist) => modlist,
+    None => return error_page("no such modlist", modlist_name),
+  };
+
+  // the scripts of a mod are in its `content/scripts` folder, while the
+  // `content` folder of the modlist is the scripts folder itself.
+  let folder = match &mod_name {
+    Some(mod_name) if is_plain_name(mod_name) => modlist
+      .mods_path()
+      .join(mod_name)
+      .join("content")
+      .join("scripts"),
+    Some(mod_name) => return error_page("invalid mod name", mod_name),
+    None => modlist.content_path(),
+  };
+
+  let script_path = match join_script(folder, &script) {
+    Some(path) if !script.is_empty() => path,
+    _ => return error_page("invalid script path", &script),
+  };
+
+  let text = match read_script(&script_path) {
+    Ok(text) => text,
+    Err(error) => return error_page(&format!("Could not read {}", script), &error.to_string()),
+  };
+
+  // a script vanilla doesn't have is compared to an empty file
+  let vanilla_path = ModList::get_by_name("vanilla")
+    .and_then(|vanilla| join_script(vanilla.content_path(), &script))
+    .filter(|path| path.is_file());
+
+  let vanilla_text = match &vanilla_path {
+    Some(path) => match read_script(path) {
+      Ok(text) => text,
+      Err(error) => return error_page("Could not read the vanilla script", &error.to_string()),
+    },
+    None => String::new(),
+  };
+
+  // a mod saved with other line endings than vanilla would differ on every
+  // line
+  let vanilla_text = normalize_line_endings(&vanilla_text);
+  let text = normalize_line_endings(&text);
+
+  let rows = number_rows(diff(&vanilla_text, &text));
+  let added = rows
+    .iter()
+    .filter(|row| matches!(row.line, DiffLine::Added(_)))
+    .count();
+  let removed = rows
+    .iter()
+    .filter(|row| matches!(row.line, DiffLine::Removed(_)))
+    .count();
+
+  let hunks = fold_rows(rows);
+
+  let url = diff_url(modlist_name, mod_name.as_deref(), &script);
+
+  let content = html! {
+    div.row.center.baseline {
+      h1 { "diff" }
+      a.small href={"/modlist/" (modlist_name)} { (modlist_name) }
+    }
+
+    p.center.path { (script) }
+
+    p.center.small {
+      (mod_name.as_deref().unwrap_or("content")) " against "
+      @if vanilla_path.is_some() { "vanilla" } @else { "an empty file, vanilla doesn't have the script" }
+      ", "
+      span.added { "+" (added) } " " span.removed { "-" (removed) } " - "
+
+      @if is_split {
+        a href=(url) { "unified" }
+      } @else {
+        a href={(url) "&view=split"} { "side by side" }
+      }
+    }
+
+    @if added == 0 && removed == 0 {
+      p.center { "the script is the same as the vanilla one" }
+    } @else if is_split {
+      (render_split(&hunks))
+    } @else {
+      (render_unified(&hunks))
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page(&format!("{} - diff", modlist_name), &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    .path {
+      font-family: monospace;
+    }
+
+    table.diff {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+      font-family: monospace;
+    }
+
+    table.diff.split {
+      width: 100%;
+      table-layout: fixed;
+    }
+
+    table.diff.split td.number {
+      width: 4em;
+    }
+
+    table.diff pre {
+      margin: 0;
+      white-space: pre-wrap;
+    }
+
+    table.diff td {
+      padding: 0 .5em;
+      vertical-align: top;
+    }
+
+    table.diff td.number {
+      color: grey;
+      text-align: right;
+      user-select: none;
+    }
+
+    table.diff tr.folded td {
+      color: grey;
+      text-align: center;
+      padding: .5em;
+    }
+
+    table.diff tr.removed, table.diff td.removed {
+      background: rgba(220, 20, 60, 0.15);
+    }
+
+    table.diff tr.added, table.diff td.added {
+      background: rgba(0, 128, 0, 0.2);
+    }
+
+    table.diff td.empty {
+      background: rgba(250, 250, 250, 0.02);
+    }
+
+    span.added {
+      color: green;
+    }
+
+    span.removed {
+      color: crimson;
+    }
+  "
+  .to_owned()
+}
@@ -0,0 +1,134 @@
+use crate::components;
+use crate::models::search_index;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use maud::html;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let query = req.query_string();
+  let query = qstring::QString::from(query);
+
+  let search_query = query.get("query").unwrap_or("").to_owned();
+
+  // a way to force a rebuild of the index, for the rare cases where a file
+  // deep inside a mod was edited in place, which doesn't change the
+  // modification times of the folders.
+  let force_refresh = query.get("refresh").is_some();
+
+  let results = match search_index::search(&search_query, force_refresh) {
+    Ok(results) => results,
+    Err(error) => {
+      let content = html! {
+        h1 { "Could not search the modlists" }
+        p { (error) }
+      };
+      let view = components::page("search", &content);
+
+      return HttpResponse::Ok()
+        .content_type("text/html")
+        .body(view.into_string());
+    }
+  };
+
+  let content = html! {
+    h1 { "search" }
+
+    (components::search_form(&search_query))
+
+    @if !search_query.is_empty() {
+      div.row.center.baseline.small {
+        span {
+          (results.hits.len()) " results"
+          @if results.truncated {
+            ", only the first " (search_index::MAX_SEARCH_HITS) " are shown"
+          }
+        }
+
+        form method="get" action="/search" {
+          input type="hidden" name="query" value=(search_query);
+          input type="hidden" name="refresh" value="1";
+          input type="submit" class="text-style" value="refresh index" title="rebuild the index before searching";
+        }
+      }
+
+      table.hits {
+        thead {
+          tr {
+            th { "modlist" }
+            th { "category" }
+            th { "entry" }
+            th { "file" }
+            th { "origin" }
+          }
+        }
+        tbody {
+          @for hit in &results.hits {
+            tr {
+              td { (components::modlist_link(&hit.modlist)) }
+              td { (hit.entry.category.as_str()) }
+              td {
+                @match &hit.imported_from {
+                  Some(origin) => {
+                    a href={"/modlist/" (origin) "/edit/" (hit.entry.category.folder_name()) "/" (hit.entry.entry)} { (hit.entry.entry) }
+                  },
+                  None => {
+                    a href={"/modlist/" (hit.modlist) "/edit/" (hit.entry.category.folder_name()) "/" (hit.entry.entry)} { (hit.entry.entry) }
+                  }
+                }
+              }
+              td.path { (hit.entry.path) }
+              td {
+                @match &hit.imported_from {
+                  Some(origin) => {
+                    span.small { "imported from " } (components::modlist_link(origin))
+                  },
+                  None => {
+                    span.accent { "local" }
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+
+    style type="text/css" { (get_stylesheet()) }
+  };
+
+  let view = components::page("search", &content);
+
+  HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+}
+
+fn get_stylesheet() -> String {
+  "
+    table.hits {
+      margin: auto;
+      border-collapse: collapse;
+      font-size: 0.8em;
+    }
+
+    table.hits th {
+      text-align: left;
+      color: grey;
+      font-weight: normal;
+    }
+
+    table.hits td, table.hits th {
+      padding: .2em 1em;
+    }
+
+    table.hits tr + tr td {
+      border-top: solid 1px rgba(250, 250, 250, 0.05);
+    }
+
+    table.hits td.path {
+      font-family: monospace;
+    }
+  "
+  .to_owned()
+}
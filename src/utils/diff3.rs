@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+
+/// a part of the output of a three-way merge
+#[derive(Debug, PartialEq)]
+pub enum Merge3Chunk<'a> {
+  /// lines both versions agree on, or that only one version changed
+  Resolved(Vec<&'a str>),
+
+  /// lines both versions changed in a different way
+  Conflict {
+    original: Vec<&'a str>,
+    ours: Vec<&'a str>,
+    theirs: Vec<&'a str>,
+  },
+}
+
+/// a line of a two-way diff
+#[derive(Debug, PartialEq)]
+pub enum DiffLine<'a> {
+  /// a line both texts have
+  Same(&'a str),
+
+  /// a line only the first text has
+  Removed(&'a str),
+
+  /// a line only the second text has
+  Added(&'a str),
+}
+
+/// splits the text in lines, the line endings are kept so that joining the
+/// lines gives the exact same text back.
+pub fn split_lines(text: &str) -> Vec<&str> {
+  text.split_inclusive('\n').collect()
+}
+
+/// whether the text uses windows line endings
+pub fn has_crlf(text: &str) -> bool {
+  text.contains("\r\n")
+}
+
+/// replaces the windows line endings with `\n`. The versions of a file can use
+/// different line endings, every line would differ without it.
+pub fn normalize_line_endings(text: &str) -> String {
+  text.replace("\r\n", "\n")
+}
+
+/// puts the windows line endings back in a text normalized with
+/// [`normalize_line_endings`].
+pub fn restore_line_endings(text: String, crlf: bool) -> String {
+  if crlf {
+    text.replace('\n', "\r\n")
+  } else {
+    text
+  }
+}
+
+/// replaces every line with a number, two lines with the same content get the
+/// same number. Comparing numbers is a lot faster than comparing strings.
+fn intern_lines<'a>(lines: &[&'a str], table: &mut HashMap<&'a str, usize>) -> Vec<usize> {
+  lines
+    .iter()
+    .map(|line| {
+      let next_id = table.len();
+
+      *table.entry(line).or_insert(next_id)
+    })
+    .collect()
+}
+
+/// computes the longest common subsequence of `a` and `b` with the Myers diff
+/// algorithm. Returns for every line of `a` the index of the line of `b` it
+/// was matched with, if any.
+fn diff_matches(a: &[usize], b: &[usize]) -> Vec<Option<usize>> {
+  let mut matches = vec![None; a.len()];
+
+  // the common prefix and suffix are matched directly, it keeps the part the
+  // Myers algorithm works on small since most mods change few lines.
+  let mut prefix = 0;
+  while prefix < a.len() && prefix < b.len() && a[prefix] == b[prefix] {
+    matches[prefix] = Some(prefix);
+    prefix += 1;
+  }
+
+  let mut suffix = 0;
+  while suffix < a.len() - prefix
+    && suffix < b.len() - prefix
+    && a[a.len() - 1 - suffix] == b[b.len() - 1 - suffix]
+  {
+    matches[a.len() - 1 - suffix] = Some(b.len() - 1 - suffix);
+    suffix += 1;
+  }
+
+  let a_middle = &a[prefix..a.len() - suffix];
+  let b_middle = &b[prefix..b.len() - suffix];
+
+  for (x, y) in myers(a_middle, b_middle) {
+    matches[prefix + x] = Some(prefix + y);
+  }
+
+  slide_down(a, b, &mut matches);
+
+  matches
+}
+
+/// moves the blocks of added or removed lines down as long as the line after
+/// the block is the same as its first line. The diff is as short, but a block
+/// like `}` `else {` ends up after the `}` it repeats rather than before it,
+/// which keeps it away from the changes of the lines above.
+fn slide_down(a: &[usize], b: &[usize], matches: &mut [Option<usize>]) {
+  // the line of `b` after the last matched line
+  let mut b_next = 0;
+  let mut i = 0;
+
+  while i < a.len() {
+    match matches[i] {
+      Some(j) => {
+        // added lines right before the matched line, and no removed lines
+        let only_added = i == 0 || matches[i - 1].is_some();
+
+        if only_added && j > b_next && b[b_next] == b[j] {
+          matches[i] = Some(b_next);
+          b_next += 1;
+        } else {
+          b_next = j + 1;
+        }
+
+        i += 1;
+      }
+      None => {
+        let mut end = i;
+        while end < a.len() && matches[end].is_none() {
+          end += 1;
+        }
+
+        // removed lines right before the matched line, and no added lines
+        if end < a.len() && matches[end] == Some(b_next) && a[i] == a[end] {
+          matches[i] = Some(b_next);
+          matches[end] = None;
+          b_next += 1;
+          i += 1;
+        } else {
+          i = end;
+        }
+      }
+    }
+  }
+}
+
+/// returns the pairs of matched indices of the shortest edit script between
+/// `a` and `b`.
+fn myers(a: &[usize], b: &[usize]) -> Vec<(usize, usize)> {
+  let n = a.len() as isize;
+  let m = b.len() as isize;
+  let max = n + m;
+
+  if max == 0 {
+    return Vec::new();
+  }
+
+  let offset = max as usize;
+  let mut v = vec![0isize; 2 * offset + 2];
+
+  // the step `d` only reads the diagonals `-d..=d` of the previous step, only
+  // these are kept or the trace would grow with the square of the file size.
+  let mut trace = Vec::new();
+
+  'search: for d in 0..=max {
+    trace.push(v[offset - d as usize..=offset + d as usize].to_vec());
+
+    let mut k = -d;
+    while k <= d {
+      let index = (k + offset as isize) as usize;
+
+      let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+        v[index + 1]
+      } else {
+        v[index - 1] + 1
+      };
+      let mut y = x - k;
+
+      while x < n && y < m && a[x as usize] == b[y as usize] {
+        x += 1;
+        y += 1;
+      }
+
+      v[index] = x;
+
+      if x >= n && y >= m {
+        break 'search;
+      }
+
+      k += 2;
+    }
+  }
+
+  // walks the trace backward to find the diagonals, which are the matches
+  let mut matches = Vec::new();
+  let mut x = n;
+  let mut y = m;
+
+  for (d, v) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    let k = x - y;
+
+    // the diagonal `k` of the step is at `k + d` in its slice
+    let (previous_x, previous_y) = if d == 0 {
+      (0, 0)
+    } else {
+      let index = (k + d) as usize;
+
+      let previous_k = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+        k + 1
+      } else {
+        k - 1
+      };
+
+      let previous_x = v[(previous_k + d) as usize];
+
+      (previous_x, previous_x - previous_k)
+    };
+
+    while x > previous_x && y > previous_y {
+      x -= 1;
+      y -= 1;
+      matches.push((x as usize, y as usize));
+    }
+
+    if d > 0 {
+      x = previous_x;
+      y = previous_y;
+    }
+  }
+
+  matches.reverse();
+
+  matches
+}
+
+/// compares the two texts line by line, the removed lines of a change come
+/// before its added lines. The texts must use the same line endings, see
+/// [`normalize_line_endings`].
+pub fn diff<'a>(before: &'a str, after: &'a str) -> Vec<DiffLine<'a>> {
+  let before = split_lines(before);
+  let after = split_lines(after);
+
+  let mut table = HashMap::new();
+  let before_ids = intern_lines(&before, &mut table);
+  let after_ids = intern_lines(&after, &mut table);
+
+  let matches = diff_matches(&before_ids, &after_ids);
+
+  let mut lines = Vec::with_capacity(before.len().max(after.len()));
+  let mut after_start = 0;
+
+  for (line, matched) in before.iter().zip(matches) {
+    match matched {
+      Some(after_index) => {
+        lines.extend(
+          after[after_start..after_index]
+            .iter()
+            .map(|line| DiffLine::Added(line)),
+        );
+        lines.push(DiffLine::Same(line));
+        after_start = after_index + 1;
+      }
+      None => lines.push(DiffLine::Removed(line)),
+    }
+  }
+
+  lines.extend(
+    after[after_start..]
+      .iter()
+      .map(|line| DiffLine::Added(line)),
+  );
+
+  lines
+}
+
+/// merges the changes `ours` and `theirs` made to `original`, line by line.
+/// The changes that touch the same lines in both versions end up in conflict
+/// chunks unless both versions made the exact same change. When both versions
+/// only add lines at the same place, the lines of `ours` come first.
+pub fn merge3<'a>(original: &'a str, ours: &'a str, theirs: &'a str) -> Vec<Merge3Chunk<'a>> {
+  let original = split_lines(original);
+  let ours = split_lines(ours);
+  let theirs = split_lines(theirs);
+
+  let mut table = HashMap::new();
+  let original_ids = intern_lines(&original, &mut table);
+  let ours_ids = intern_lines(&ours, &mut table);
+  let theirs_ids = intern_lines(&theirs, &mut table);
+
+  let ours_matches = diff_matches(&original_ids, &ours_ids);
+  let theirs_matches = diff_matches(&original_ids, &theirs_ids);
+
+  let mut chunks = Vec::new();
+  let (mut original_start, mut ours_start, mut theirs_start) = (0, 0, 0);
+
+  loop {
+    // the next original line that is in both versions
+    let mut original_end = original_start;
+    while original_end < original.len()
+      && (ours_matches[original_end].is_none() || theirs_matches[original_end].is_none())
+    {
+      original_end += 1;
+    }
+
+    let (ours_end, theirs_end) = if original_end < original.len() {
+      (
+        ours_matches[original_end].unwrap_or(ours.len()),
+        theirs_matches[original_end].unwrap_or(theirs.len()),
+      )
+    } else {
+      (ours.len(), theirs.len())
+    };
+
+    // the lines before it are changed in at least one of the versions
+    let original_chunk = &original[original_start..original_end];
+    let ours_chunk = &ours[ours_start..ours_end];
+    let theirs_chunk = &theirs[theirs_start..theirs_end];
+
+    push_unstable_chunk(&mut chunks, original_chunk, ours_chunk, theirs_chunk);
+
+    if original_end >= original.len() {
+      break;
+    }
+
+    // then the lines all three agree on
+    let mut length = 0;
+    while original_end + length < original.len()
+      && ours_matches[original_end + length] == Some(ours_end + length)
+      && theirs_matches[original_end + length] == Some(theirs_end + length)
+    {
+      length += 1;
+    }
+
+    push_resolved(&mut chunks, &original[original_end..original_end + length]);
+
+    original_start = original_end + length;
+    ours_start = ours_end + length;
+    theirs_start = theirs_end + length;
+  }
+
+  chunks
+}
+
+fn push_resolved<'a>(chunks: &mut Vec<Merge3Chunk<'a>>, lines: &[&'a str]) {
+  if lines.is_empty() {
+    return;
+  }
+
+  if let Some(Merge3Chunk::Resolved(previous)) = chunks.last_mut() {
+    previous.extend_from_slice(lines);
+  } else {
+    chunks.push(Merge3Chunk::Resolved(lines.to_vec()));
+  }
+}
+
+fn push_unstable_chunk<'a>(
+  chunks: &mut Vec<Merge3Chunk<'a>>, original: &[&'a str], ours: &[&'a str], theirs: &[&'a str],
+) {
+  if original.is_empty() && ours.is_empty() && theirs.is_empty() {
+    return;
+  }
+
+  if ours == original {
+    push_resolved(chunks, theirs);
+  } else if theirs == original || ours == theirs {
+    push_resolved(chunks, ours);
+  } else if original.is_empty() {
+    // both versions added lines at the same place, both are kept
+    push_resolved(chunks, ours);
+    push_resolved(chunks, theirs);
+  } else {
+    chunks.push(Merge3Chunk::Conflict {
+      original: original.to_vec(),
+      ours: ours.to_vec(),
+      theirs: theirs.to_vec(),
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// the text of the merge, or `None` if it has conflicts
+  fn merge_text(original: &str, ours: &str, theirs: &str) -> Option<String> {
+    let mut output = String::new();
+
+    for chunk in merge3(original, ours, theirs) {
+      match chunk {
+        Merge3Chunk::Resolved(lines) => output.extend(lines),
+        Merge3Chunk::Conflict { .. } => return None,
+      }
+    }
+
+    Some(output)
+  }
+
+  #[test]
+  fn diff_finds_added_and_removed_lines() {
+    let lines = diff("a\nb\nc\n", "a\nc\nd\n");
+
+    assert_eq!(
+      lines,
+      vec![
+        DiffLine::Same("a\n"),
+        DiffLine::Removed("b\n"),
+        DiffLine::Same("c\n"),
+        DiffLine::Added("d\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_of_identical_texts_has_no_changes() {
+    let lines = diff("a\nb\n", "a\nb\n");
+
+    assert!(lines.iter().all(|line| matches!(line, DiffLine::Same(_))));
+  }
+
+  #[test]
+  fn diff_puts_repeated_added_lines_after_the_lines_they_repeat() {
+    let lines = diff("if {\n}\n}\n", "if {\n}\nelse {\n}\n}\n");
+
+    assert_eq!(
+      lines,
+      vec![
+        DiffLine::Same("if {\n"),
+        DiffLine::Same("}\n"),
+        DiffLine::Added("else {\n"),
+        DiffLine::Added("}\n"),
+        DiffLine::Same("}\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_of_large_texts() {
+    let before = (0..4000)
+      .map(|i| format!("line {}\n", i))
+      .collect::<String>();
+    let after = (0..4000)
+      .map(|i| format!("line {}\n", i * 2))
+      .collect::<String>();
+
+    let lines = diff(&before, &after);
+    let same = lines
+      .iter()
+      .filter(|line| matches!(line, DiffLine::Same(_)))
+      .count();
+
+    assert_eq!(same, 2000);
+  }
+
+  #[test]
+  fn merge3_takes_the_changes_of_both_versions() {
+    let merged = merge_text("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n");
+
+    assert_eq!(merged.as_deref(), Some("A\nb\nC\n"));
+  }
+
+  #[test]
+  fn merge3_keeps_the_same_change_once() {
+    let merged = merge_text("a\nb\n", "a\nB\n", "a\nB\n");
+
+    assert_eq!(merged.as_deref(), Some("a\nB\n"));
+  }
+
+  #[test]
+  fn merge3_conflicts_on_different_changes_of_the_same_line() {
+    let chunks = merge3("a\nb\nc\n", "a\nB\nc\n", "a\nX\nc\n");
+
+    assert_eq!(
+      chunks,
+      vec![
+        Merge3Chunk::Resolved(vec!["a\n"]),
+        Merge3Chunk::Conflict {
+          original: vec!["b\n"],
+          ours: vec!["B\n"],
+          theirs: vec!["X\n"],
+        },
+        Merge3Chunk::Resolved(vec!["c\n"]),
+      ]
+    );
+  }
+
+  #[test]
+  fn merge3_keeps_both_insertions_at_the_same_place() {
+    let merged = merge_text("a\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+
+    assert_eq!(merged.as_deref(), Some("a\nours\ntheirs\nc\n"));
+  }
+
+  #[test]
+  fn merge3_of_normalized_line_endings() {
+    let original = normalize_line_endings("a\r\nb\r\nc\r\n");
+    let ours = normalize_line_endings("a\r\nb\r\nC\r\n");
+    let theirs = normalize_line_endings("A\nb\nc\n");
+
+    let merged = merge_text(&original, &ours, &theirs).unwrap();
+
+    assert_eq!(restore_line_endings(merged, true), "A\r\nb\r\nC\r\n");
+  }
+
+  #[test]
+  fn merge3_of_the_spec_example() {
+    let original = "function foo() {
+  var a: bool;
+
+  a = this.should_a();
+  
+  if (a) {
+    // ...
+  }
+}
+";
+
+    let ours = "function foo() {
+  var a: bool;
+  var b: bool;
+
+  a = this.should_a();
+  b = this.should_b();
+  
+  if (a && b) {
+    // ...
+  }
+}
+";
+
+    let theirs = "function foo() {
+  var a: bool;
+  var c: bool;
+
+  a = this.should_a();
+  
+  if (a) {
+    // ...
+  }
+  else {
+    c = false;
+    // ...
+  }
+}
+";
+
+    let expected = "function foo() {
+  var a: bool;
+  var b: bool;
+  var c: bool;
+
+  a = this.should_a();
+  b = this.should_b();
+  
+  if (a && b) {
+    // ...
+  }
+  else {
+    c = false;
+    // ...
+  }
+}
+";
+
+    assert_eq!(
+      merge_text(original, ours, theirs).as_deref(),
+      Some(expected)
+    );
+  }
+}
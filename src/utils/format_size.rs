@@ -0,0 +1,17 @@
+/// formats a size in bytes into a human readable string, like `1.5 GB`
+pub fn format_size(size: u64) -> String {
+  let units = ["B", "KB", "MB", "GB", "TB"];
+  let mut value = size as f64;
+  let mut unit = 0;
+
+  while value >= 1024.0 && unit < units.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+
+  if unit == 0 {
+    format!("{} {}", size, units[unit])
+  } else {
+    format!("{:.1} {}", value, units[unit])
+  }
+}
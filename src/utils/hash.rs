@@ -0,0 +1,28 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// returns the sha1 of the bytes as an hexadecimal string
+pub fn hash_bytes(bytes: &[u8]) -> String {
+  sha1_smol::Sha1::from(bytes).digest().to_string()
+}
+
+/// returns the sha1 of the file content as an hexadecimal string, the file is
+/// read in chunks so large files are not loaded in memory at once.
+pub fn hash_file(path: &PathBuf) -> std::io::Result<String> {
+  let mut file = File::open(path)?;
+  let mut hasher = sha1_smol::Sha1::new();
+  let mut buffer = vec![0; 64 * 1024];
+
+  loop {
+    let read = file.read(&mut buffer)?;
+
+    if read == 0 {
+      break;
+    }
+
+    hasher.update(&buffer[..read]);
+  }
+
+  Ok(hasher.digest().to_string())
+}
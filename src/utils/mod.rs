@@ -1,5 +1,10 @@
 pub mod api_error;
+pub mod diff3;
+pub mod hash;
 pub mod symlinks;
 
 mod copy_across_drives;
 pub use copy_across_drives::copy_across_drives;
+
+mod format_size;
+pub use format_size::format_size;
@@ -94,8 +94,8 @@ pub fn remove_symlink(path: &PathBuf) -> std::io::Result<()> {
     })
     .and_then(|_| fs::read_link(&path))
     .and_then(|link| match link.is_dir() {
-      // It's a symlink and a directory
-      true => fs::remove_dir(path),
+      // It's a symlink and a directory, only windows removes them as directories
+      true => fs::remove_dir(path).or_else(|_| fs::remove_file(path)),
       // It's a symlink and a file
       false => {
         println!("removing symlink {:?}", &path);
@@ -111,6 +111,32 @@ pub fn remove_symlink(path: &PathBuf) -> std::io::Result<()> {
     })
 }
 
+/// writes the file, the symlink is removed first if the path is one. The files
+/// of a modlist can be symlinks to the files of an imported modlist, which
+/// must not be written through.
+pub fn write_replacing_symlink(path: &PathBuf, content: impl AsRef<[u8]>) -> std::io::Result<()> {
+  // fails when the path is not a symlink, which is fine
+  let _ = remove_symlink(path);
+
+  fs::write(path, content)
+}
+
+/// returns whether at least one of the children in the directory is a symlink
+pub fn has_symlinks(directory: &PathBuf) -> bool {
+  let children = match fs::read_dir(&directory) {
+    Ok(children) => children,
+    Err(_) => return false,
+  };
+
+  children.filter_map(|child| child.ok()).any(|child| {
+    child
+      .path()
+      .symlink_metadata()
+      .map(|metadata| metadata.file_type().is_symlink())
+      .unwrap_or(false)
+  })
+}
+
 pub fn get_children_without_symlinks(directory: &PathBuf) -> std::io::Result<Vec<String>> {
   let children = fs::read_dir(&directory)?;
   let mut output = Vec::new();